@@ -0,0 +1,45 @@
+//! the `bundle` submodule combines the instructions needed to deposit into a v2 vault,
+//! registering the depositor's deposit tracking account when this is their first deposit
+
+use super::traits::{IssueShares, RegisterDepositTracking};
+use solana_program::instruction::Instruction;
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use tulipv2_sdk_farms::Farm;
+
+/// returns the instructions which deposit `amount` of the underlying asset into a vault.
+///
+/// when `deposit_tracking_exists` is false the deposit tracking hold account is created,
+/// and the deposit tracking account registered, before the `issue_shares` instruction.
+/// returns `None` if any of the instructions could not be built
+pub fn new_deposit_bundle_ixs<R, I>(
+    register: &R,
+    issue: &I,
+    farm_type: Farm,
+    amount: u64,
+    deposit_tracking_exists: bool,
+) -> Option<Vec<Instruction>>
+where
+    R: RegisterDepositTracking + ?Sized,
+    I: IssueShares + ?Sized,
+{
+    let mut instructions = Vec::with_capacity(3);
+    if !deposit_tracking_exists {
+        instructions.push(new_create_hold_account_ix(register));
+        instructions.push(register.instruction(farm_type)?);
+    }
+    instructions.push(issue.instruction(farm_type, amount)?);
+    Some(instructions)
+}
+
+/// returns the instruction creating the deposit tracking hold account, which the
+/// `register_deposit_tracking` instruction expects to exist, paid for by the authority
+pub fn new_create_hold_account_ix<R: RegisterDepositTracking + ?Sized>(
+    register: &R,
+) -> Instruction {
+    create_associated_token_account_idempotent(
+        &register.authority(),
+        &register.deposit_tracking_pda(),
+        &register.shares_mint(),
+        &spl_token::id(),
+    )
+}
@@ -5,10 +5,14 @@
 //!
 //! The `traits` submodule provides a set of traits used to easily return accounts needed
 //! for various instructions, while the `derivations` submodule contains functions used
-//! to derive deposit tracking addresses
+//! to derive deposit tracking addresses. the `bundle` submodule combines the instructions
+//! needed for a deposit, including a depositor's first deposit, and the `vault_config`
+//! submodule provides the base configuration trait shared by the v2 vaults
 
+pub mod bundle;
 pub mod derivations;
 pub mod issue_shares;
 pub mod register;
 pub mod traits;
+pub mod vault_config;
 pub mod withdraw;
@@ -0,0 +1,49 @@
+//! the `vault_config` submodule provides the base configuration shared by the v2 vaults,
+//! along with the deposit bundle every vault builds the same way
+
+use super::bundle::new_deposit_bundle_ixs;
+use super::issue_shares::DepositAddresses;
+use super::register::RegisterDepositTrackingAddresses;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use solana_program::instruction::Instruction;
+use tulipv2_sdk_farms::Farm;
+
+/// base configuration accounts used across all vault types
+pub trait VaultBaseConfig {
+    fn vault(&self) -> Pubkey;
+    fn vault_pda(&self) -> Pubkey;
+    fn withdraw_queue(&self) -> Pubkey;
+    fn compound_queue(&self) -> Pubkey;
+    fn deposit_queue(&self) -> Pubkey;
+    fn shares_mint(&self) -> Pubkey;
+    /// returns the instructions which deposit `amount` of `underlying_mint` into the vault,
+    /// creating and registering the deposit tracking account first when
+    /// `deposit_tracking_exists` is false
+    fn deposit_bundle(
+        &self,
+        authority: Pubkey,
+        underlying_mint: Pubkey,
+        farm_type: Farm,
+        amount: u64,
+        deposit_tracking_exists: bool,
+    ) -> Option<Vec<Instruction>> {
+        new_deposit_bundle_ixs(
+            &RegisterDepositTrackingAddresses::new(
+                authority,
+                self.vault(),
+                self.shares_mint(),
+                underlying_mint,
+            ),
+            &DepositAddresses::new(
+                authority,
+                self.vault(),
+                self.vault_pda(),
+                self.shares_mint(),
+                underlying_mint,
+            ),
+            farm_type,
+            amount,
+            deposit_tracking_exists,
+        )
+    }
+}
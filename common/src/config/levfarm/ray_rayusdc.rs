@@ -33,6 +33,9 @@ pub mod vault_config {
         static_pubkey!("5KQFnDd33J5NaMC9hQ64P5XzaaSz8Pt7NBCkZFYn1po");
     pub const LP_TOKEN_ACCOUNT: Pubkey =
         static_pubkey!("E8gJAEcHDB4be9sCKSytLUyBe3V5SEDHgn4192REJhaB");
+    pub const POOL_ID: Pubkey = static_pubkey!("CHYrUBX2RKX8iBg7gYTkccoGNBzP44LdaazMHCLcdEgS");
+    pub const POOL_LP_TOKEN_ACCOUNT: Pubkey =
+        static_pubkey!("BNnXLFGva3K8ACruAc1gaP49NCbLkyE6xWhGV4G2HLrs");
     // set to this if not used
     // pub const OLD_VAULT_INFO: Pubkey = DEFAULT_KEY;
     pub const OLD_VAULT_INFO_ACCOUNT: Pubkey =
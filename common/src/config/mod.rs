@@ -33,3 +33,5 @@ pub const RAYDIUM_LIQUIDITY_V4: Pubkey =
 pub const RAYDIUM_STAKE: Pubkey = static_pubkey!("EhhTKczWMGQt46ynNeRX1WfeagwwJd7ufHvCDjRxjo5Q");
 /// address of the latest raydium staking program
 pub const RAYDIUM_STAKE_V5: Pubkey = static_pubkey!("9KEPoZmtHUrBbhWN1v1KWLMkkvwY6WLtAVUCPRtRjP4z");
+/// address of the serum dex v3 program
+pub const SERUM_DEX_V3: Pubkey = static_pubkey!("9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin");
@@ -17,7 +17,7 @@ pub mod ray;
 #[cfg(feature = "usdt-optimizer")]
 pub mod usdt;
 
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Platform {
     MangoV3,
     Tulip,
@@ -769,4 +769,38 @@ mod test {
             }
         );
     }
+
+    #[test]
+    fn test_multi_deposit_bundle() {
+        let user = anchor_lang::solana_program::pubkey::Pubkey::new_unique();
+        let conf = StrategyVaults::USDCv1.multi_deposit_config();
+
+        let ixs = conf.deposit_bundle(user, 1_000_000, false).unwrap();
+        assert_eq!(ixs.len(), 3);
+        assert_eq!(ixs[0].program_id, spl_associated_token_account::id());
+        assert_eq!(
+            ixs[1].data,
+            conf.register_deposit_tracking(user)
+                .instruction(conf.farm())
+                .unwrap()
+                .data
+        );
+        assert_eq!(
+            ixs[2].data,
+            conf.issue_shares(user)
+                .instruction(conf.farm(), 1_000_000)
+                .unwrap()
+                .data
+        );
+
+        let ixs = conf.deposit_bundle(user, 1_000_000, true).unwrap();
+        assert_eq!(ixs.len(), 1);
+        assert_eq!(
+            ixs[0].data,
+            conf.issue_shares(user)
+                .instruction(conf.farm(), 1_000_000)
+                .unwrap()
+                .data
+        );
+    }
 }
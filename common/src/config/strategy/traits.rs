@@ -5,6 +5,7 @@ use solana_program::instruction::Instruction;
 use tulipv2_sdk_farms::Farm;
 
 use super::Platform;
+use crate::config::deposit_tracking::bundle::new_deposit_bundle_ixs;
 use crate::config::deposit_tracking::traits::WithdrawDepositTracking;
 use crate::config::deposit_tracking::traits::{IssueShares, RegisterDepositTracking};
 
@@ -159,6 +160,23 @@ pub trait MultiVaultProgramConfig {
     fn farm(&self) -> Farm;
     /// returns the stringified tag of the multi deposit vault
     fn tag(&self) -> &str;
+    /// returns the instructions which deposit `amount` of the underlying asset into the
+    /// multi deposit vault, creating and registering the deposit tracking account first
+    /// when `deposit_tracking_exists` is false
+    fn deposit_bundle(
+        &self,
+        user: Pubkey,
+        amount: u64,
+        deposit_tracking_exists: bool,
+    ) -> Option<Vec<Instruction>> {
+        new_deposit_bundle_ixs(
+            self.register_deposit_tracking(user).as_ref(),
+            self.issue_shares(user).as_ref(),
+            self.farm(),
+            amount,
+            deposit_tracking_exists,
+        )
+    }
 }
 
 /// Trait type that is used to return configuration information, instruction helpers, etc...
@@ -0,0 +1,274 @@
+use super::error::LendingError;
+use super::reserve::Reserve;
+use super::*;
+use anchor_lang::solana_program::{
+    msg,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::{Pubkey, PUBKEY_BYTES},
+};
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+
+/// Lending market state
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LendingMarket {
+    /// Version of lending market
+    pub version: u8,
+    /// Bump seed for derived authority address
+    pub bump_seed: u8,
+    /// Owner authority which can add new reserves
+    pub owner: Pubkey,
+    /// Currency market prices are quoted in
+    /// e.g. "USD" null padded (`*b"USD\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0"`) or a SPL token mint pubkey
+    pub quote_currency: [u8; 32],
+    /// Token program id
+    pub token_program_id: Pubkey,
+    /// Oracle (Pyth) program id
+    pub oracle_program_id: Pubkey,
+}
+
+impl LendingMarket {
+    /// Create a new lending market
+    pub fn new(params: InitLendingMarketParams) -> Self {
+        let mut lending_market = Self::default();
+        Self::init(&mut lending_market, params);
+        lending_market
+    }
+
+    /// Initialize a lending market
+    pub fn init(&mut self, params: InitLendingMarketParams) {
+        self.version = PROGRAM_VERSION;
+        self.bump_seed = params.bump_seed;
+        self.owner = params.owner;
+        self.quote_currency = params.quote_currency;
+        self.token_program_id = params.token_program_id;
+        self.oracle_program_id = params.oracle_program_id;
+    }
+
+    /// Returns the quote currency as a symbol, ie: "USD", if it is null padded utf8
+    pub fn quote_currency_symbol(&self) -> Option<&str> {
+        let len = self
+            .quote_currency
+            .iter()
+            .position(|byte| *byte == 0)
+            .unwrap_or(self.quote_currency.len());
+        if len == 0 || self.quote_currency[len..].iter().any(|byte| *byte != 0) {
+            return None;
+        }
+        std::str::from_utf8(&self.quote_currency[..len]).ok()
+    }
+
+    /// Returns the quote currency as a SPL token mint pubkey
+    pub fn quote_currency_mint(&self) -> Pubkey {
+        Pubkey::new_from_array(self.quote_currency)
+    }
+
+    /// Derives the lending market authority from the stored bump seed, and checks that
+    /// it matches `lending_market_authority`
+    pub fn verify_authority(
+        &self,
+        program_id: &Pubkey,
+        lending_market: &Pubkey,
+        lending_market_authority: &Pubkey,
+    ) -> Result<(), ProgramError> {
+        let authority_signer_seeds = &[
+            &lending_market.to_bytes()[..PUBKEY_BYTES],
+            &[self.bump_seed],
+        ];
+        let expected_authority = Pubkey::create_program_address(authority_signer_seeds, program_id)
+            .map_err(|_| LendingError::InvalidMarketAuthority)?;
+        if &expected_authority != lending_market_authority {
+            msg!(
+                "Derived lending market authority {} does not match the lending market authority provided {}",
+                expected_authority,
+                lending_market_authority
+            );
+            return Err(LendingError::InvalidMarketAuthority.into());
+        }
+        Ok(())
+    }
+
+    /// Checks that the reserve belongs to the lending market at `lending_market`
+    pub fn verify_reserve(lending_market: &Pubkey, reserve: &Reserve) -> Result<(), ProgramError> {
+        if &reserve.lending_market != lending_market {
+            msg!(
+                "Reserve lending market {} does not match the lending market provided {}",
+                reserve.lending_market,
+                lending_market
+            );
+            return Err(LendingError::InvalidAccountInput.into());
+        }
+        Ok(())
+    }
+}
+
+/// Derives the lending market authority, and its bump seed
+pub fn derive_lending_market_authority(
+    program_id: &Pubkey,
+    lending_market: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[&lending_market.to_bytes()[..PUBKEY_BYTES]], program_id)
+}
+
+/// Initialize a lending market
+pub struct InitLendingMarketParams {
+    /// Bump seed for derived authority address
+    pub bump_seed: u8,
+    /// Owner authority which can add new reserves
+    pub owner: Pubkey,
+    /// Currency market prices are quoted in
+    /// e.g. "USD" null padded (`*b"USD\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0"`) or a SPL token mint pubkey
+    pub quote_currency: [u8; 32],
+    /// Token program id
+    pub token_program_id: Pubkey,
+    /// Oracle (Pyth) program id
+    pub oracle_program_id: Pubkey,
+}
+
+impl Sealed for LendingMarket {}
+impl IsInitialized for LendingMarket {
+    fn is_initialized(&self) -> bool {
+        self.version != UNINITIALIZED_VERSION
+    }
+}
+
+const LENDING_MARKET_LEN: usize = 258; // 1 + 1 + 32 + 32 + 32 + 32 + 128
+impl Pack for LendingMarket {
+    const LEN: usize = LENDING_MARKET_LEN;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, LENDING_MARKET_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            version,
+            bump_seed,
+            owner,
+            quote_currency,
+            token_program_id,
+            oracle_program_id,
+            _padding,
+        ) = mut_array_refs![
+            output,
+            1,
+            1,
+            PUBKEY_BYTES,
+            32,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            128
+        ];
+
+        *version = self.version.to_le_bytes();
+        *bump_seed = self.bump_seed.to_le_bytes();
+        owner.copy_from_slice(self.owner.as_ref());
+        quote_currency.copy_from_slice(self.quote_currency.as_ref());
+        token_program_id.copy_from_slice(self.token_program_id.as_ref());
+        oracle_program_id.copy_from_slice(self.oracle_program_id.as_ref());
+    }
+
+    /// Unpacks a byte buffer into a [LendingMarket](struct.LendingMarket.html)
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, LENDING_MARKET_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            version,
+            bump_seed,
+            owner,
+            quote_currency,
+            token_program_id,
+            oracle_program_id,
+            _padding,
+        ) = array_refs![
+            input,
+            1,
+            1,
+            PUBKEY_BYTES,
+            32,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            128
+        ];
+
+        let version = u8::from_le_bytes(*version);
+        if version > PROGRAM_VERSION {
+            msg!("Lending market version does not match lending program version");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(Self {
+            version,
+            bump_seed: u8::from_le_bytes(*bump_seed),
+            owner: Pubkey::new_from_array(*owner),
+            quote_currency: *quote_currency,
+            token_program_id: Pubkey::new_from_array(*token_program_id),
+            oracle_program_id: Pubkey::new_from_array(*oracle_program_id),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn usd() -> [u8; 32] {
+        let mut quote_currency = [0_u8; 32];
+        quote_currency[..3].copy_from_slice(b"USD");
+        quote_currency
+    }
+
+    #[test]
+    fn test_pack_unpack() {
+        let program_id = Pubkey::new_unique();
+        let lending_market_key = Pubkey::new_unique();
+        let (authority, bump_seed) =
+            derive_lending_market_authority(&program_id, &lending_market_key);
+        let lending_market = LendingMarket::new(InitLendingMarketParams {
+            bump_seed,
+            owner: Pubkey::new_unique(),
+            quote_currency: usd(),
+            token_program_id: Pubkey::new_unique(),
+            oracle_program_id: Pubkey::new_unique(),
+        });
+
+        let mut data = vec![0_u8; LendingMarket::LEN];
+        LendingMarket::pack(lending_market.clone(), &mut data).unwrap();
+        let unpacked = LendingMarket::unpack(&data).unwrap();
+        assert_eq!(unpacked, lending_market);
+        assert!(unpacked.is_initialized());
+        assert_eq!(unpacked.quote_currency_symbol(), Some("USD"));
+
+        unpacked
+            .verify_authority(&program_id, &lending_market_key, &authority)
+            .unwrap();
+        assert!(unpacked
+            .verify_authority(&program_id, &lending_market_key, &Pubkey::new_unique())
+            .is_err());
+
+        let mut reserve = Reserve {
+            lending_market: lending_market_key,
+            ..Default::default()
+        };
+        LendingMarket::verify_reserve(&lending_market_key, &reserve).unwrap();
+        reserve.lending_market = Pubkey::new_unique();
+        assert!(LendingMarket::verify_reserve(&lending_market_key, &reserve).is_err());
+    }
+
+    #[test]
+    fn test_quote_currency() {
+        let mint = Pubkey::new_unique();
+        let lending_market = LendingMarket {
+            quote_currency: mint.to_bytes(),
+            ..Default::default()
+        };
+        assert_eq!(lending_market.quote_currency_mint(), mint);
+
+        let mut quote_currency = usd();
+        quote_currency[5] = 1;
+        let lending_market = LendingMarket {
+            quote_currency,
+            ..Default::default()
+        };
+        assert_eq!(lending_market.quote_currency_symbol(), None);
+        assert_eq!(LendingMarket::default().quote_currency_symbol(), None);
+    }
+}
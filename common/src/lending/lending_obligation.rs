@@ -72,6 +72,15 @@ impl LendingObligation {
         self.borrowed_value.try_div(self.deposited_value)
     }
 
+    /// Calculate the ratio of unhealthy borrow value to borrowed value. below 1 the obligation
+    /// can be liquidated, obligations without borrows are given a health of u64::MAX
+    pub fn health(&self) -> Result<Decimal, ProgramError> {
+        if self.borrowed_value == Decimal::zero() {
+            return Ok(Decimal::from(u64::MAX));
+        }
+        self.unhealthy_borrow_value.try_div(self.borrowed_value)
+    }
+
     /// Repay liquidity and remove it from borrows if zeroed out
     pub fn repay(&mut self, settle_amount: Decimal, liquidity_index: usize) -> ProgramResult {
         let liquidity = &mut self.borrows[liquidity_index];
@@ -0,0 +1,373 @@
+//! off-chain liquidation price calculations for lending obligations.
+//!
+//! the borrowed value, and unhealthy borrow value of an obligation are both linear in the
+//! price of any single asset, so the liquidation price of an asset is solved for by refreshing
+//! the obligation at two prices, and finding where the two lines intersect.
+
+use super::lending_obligation::{pseudo_refresh_lending_obligation, LendingObligation};
+use super::reserve::Reserve;
+use crate::math::{
+    common::{TryAdd, TryDiv, TryMul, TrySub},
+    decimal::Decimal,
+};
+use anchor_lang::solana_program::{msg, program_error::ProgramError, pubkey::Pubkey};
+use std::collections::HashMap;
+
+/// The direction in which the price of an asset must move for an obligation to be liquidated
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LiquidationDirection {
+    /// the obligation is liquidated when the price falls to or below the liquidation price
+    PriceFalls,
+    /// the obligation is liquidated when the price rises to or above the liquidation price
+    PriceRises,
+    /// the obligation can already be liquidated at the current price
+    AlreadyUnhealthy,
+    /// no price of the asset alone can cause the obligation to be liquidated
+    Never,
+}
+
+/// The liquidation price of an asset, with the prices of all other assets held constant
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LiquidationPrice {
+    /// Liquidity mint of the asset
+    pub mint: Pubkey,
+    /// Price of the asset used by the reserves
+    pub current_price: Decimal,
+    /// Price at which the obligation can be liquidated, None when the
+    /// direction is `AlreadyUnhealthy` or `Never`
+    pub liquidation_price: Option<Decimal>,
+    pub direction: LiquidationDirection,
+}
+
+/// Obligation health over a grid of prices for two assets, such as the coin and pc
+/// tokens of a leveraged farm
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LiquidationSurface {
+    pub x_mint: Pubkey,
+    pub y_mint: Pubkey,
+    pub x_prices: Vec<Decimal>,
+    pub y_prices: Vec<Decimal>,
+    /// obligation health indexed by `[x][y]`, see `LendingObligation::health`
+    pub health: Vec<Vec<Decimal>>,
+    /// liquidation price of the y asset at each of the x prices
+    pub y_liquidation_prices: Vec<LiquidationPrice>,
+}
+
+/// Calculate the price of the asset with the given liquidity mint at which the obligation
+/// can be liquidated, holding the prices of all other assets constant
+pub fn calculate_liquidation_price(
+    obligation: &LendingObligation,
+    reserves: &HashMap<Pubkey, Reserve>,
+    mint: Pubkey,
+) -> Result<LiquidationPrice, ProgramError> {
+    let current_price = current_market_price(reserves, mint)?;
+    solve_liquidation_price(obligation, reserves, mint, current_price)
+}
+
+/// Calculate the obligation health over a grid of prices for two assets, along with the
+/// liquidation price of the y asset at each of the x prices
+pub fn calculate_liquidation_surface(
+    obligation: &LendingObligation,
+    reserves: &HashMap<Pubkey, Reserve>,
+    x_mint: Pubkey,
+    x_prices: &[Decimal],
+    y_mint: Pubkey,
+    y_prices: &[Decimal],
+) -> Result<LiquidationSurface, ProgramError> {
+    let current_y_price = current_market_price(reserves, y_mint)?;
+    let mut health = Vec::with_capacity(x_prices.len());
+    let mut y_liquidation_prices = Vec::with_capacity(x_prices.len());
+    for x_price in x_prices {
+        let x_reserves = reserves_at_price(reserves, x_mint, *x_price);
+        let mut row = Vec::with_capacity(y_prices.len());
+        for y_price in y_prices {
+            let obligation = refresh_at_price(obligation, &x_reserves, y_mint, *y_price)?;
+            row.push(obligation.health()?);
+        }
+        health.push(row);
+        y_liquidation_prices.push(solve_liquidation_price(
+            obligation,
+            &x_reserves,
+            y_mint,
+            current_y_price,
+        )?);
+    }
+    Ok(LiquidationSurface {
+        x_mint,
+        y_mint,
+        x_prices: x_prices.to_vec(),
+        y_prices: y_prices.to_vec(),
+        health,
+        y_liquidation_prices,
+    })
+}
+
+fn solve_liquidation_price(
+    obligation: &LendingObligation,
+    reserves: &HashMap<Pubkey, Reserve>,
+    mint: Pubkey,
+    current_price: Decimal,
+) -> Result<LiquidationPrice, ProgramError> {
+    let mut result = LiquidationPrice {
+        mint,
+        current_price,
+        liquidation_price: None,
+        direction: LiquidationDirection::Never,
+    };
+
+    let current = refresh_at_price(obligation, reserves, mint, current_price)?;
+    if current.borrowed_value > Decimal::zero()
+        && current.borrowed_value >= current.unhealthy_borrow_value
+    {
+        result.direction = LiquidationDirection::AlreadyUnhealthy;
+        return Ok(result);
+    }
+
+    // borrowed = borrowed_base + borrowed_slope * price
+    // unhealthy = unhealthy_base + unhealthy_slope * price
+    let base = refresh_at_price(obligation, reserves, mint, Decimal::zero())?;
+    let unit = refresh_at_price(obligation, reserves, mint, Decimal::one())?;
+    let borrowed_slope = unit.borrowed_value.try_sub(base.borrowed_value)?;
+    let unhealthy_slope = unit
+        .unhealthy_borrow_value
+        .try_sub(base.unhealthy_borrow_value)?;
+
+    if unhealthy_slope > borrowed_slope {
+        // net long the asset, liquidated as the price falls
+        if base.borrowed_value > base.unhealthy_borrow_value {
+            result.liquidation_price = Some(
+                base.borrowed_value
+                    .try_sub(base.unhealthy_borrow_value)?
+                    .try_div(unhealthy_slope.try_sub(borrowed_slope)?)?,
+            );
+            result.direction = LiquidationDirection::PriceFalls;
+        }
+    } else if borrowed_slope > unhealthy_slope {
+        // net short the asset, liquidated as the price rises
+        result.liquidation_price = Some(
+            base.unhealthy_borrow_value
+                .try_sub(base.borrowed_value)?
+                .try_div(borrowed_slope.try_sub(unhealthy_slope)?)?,
+        );
+        result.direction = LiquidationDirection::PriceRises;
+    }
+    Ok(result)
+}
+
+/// refreshes a copy of the obligation with the market price of every reserve
+/// for the given mint set to `price`
+fn refresh_at_price(
+    obligation: &LendingObligation,
+    reserves: &HashMap<Pubkey, Reserve>,
+    mint: Pubkey,
+    price: Decimal,
+) -> Result<LendingObligation, ProgramError> {
+    let reserves = reserves_at_price(reserves, mint, price);
+    let mut obligation = obligation.clone();
+    pseudo_refresh_lending_obligation(&mut obligation, &reserves)?;
+    Ok(obligation)
+}
+
+fn reserves_at_price(
+    reserves: &HashMap<Pubkey, Reserve>,
+    mint: Pubkey,
+    price: Decimal,
+) -> HashMap<Pubkey, Reserve> {
+    reserves
+        .iter()
+        .map(|(key, reserve)| {
+            let mut reserve = reserve.clone();
+            if reserve.liquidity.mint_pubkey == mint {
+                reserve.liquidity.market_price = price;
+            }
+            (*key, reserve)
+        })
+        .collect()
+}
+
+fn current_market_price(
+    reserves: &HashMap<Pubkey, Reserve>,
+    mint: Pubkey,
+) -> Result<Decimal, ProgramError> {
+    match reserves
+        .values()
+        .find(|reserve| reserve.liquidity.mint_pubkey == mint)
+    {
+        Some(reserve) => Ok(reserve.liquidity.market_price),
+        None => {
+            msg!("failed to find reserve for mint {}", mint);
+            Err(ProgramError::InvalidArgument)
+        }
+    }
+}
+
+/// returns `points` evenly spaced prices between `min` and `max` inclusive,
+/// intended for building the axes of a liquidation surface.
+///
+/// returns an error if fewer than two points are requested, or `max` is below `min`
+pub fn price_range(min: Decimal, max: Decimal, points: u64) -> Result<Vec<Decimal>, ProgramError> {
+    if points < 2 {
+        msg!(
+            "a price range needs at least two points, {} requested",
+            points
+        );
+        return Err(ProgramError::InvalidArgument);
+    }
+    if max < min {
+        msg!("price range maximum {} is below its minimum {}", max, min);
+        return Err(ProgramError::InvalidArgument);
+    }
+    let step = max.try_sub(min)?.try_div(points - 1)?;
+    (0..points)
+        .map(|idx| min.try_add(step.try_mul(idx)?))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lending::lending_obligation::{
+        LendingObligationCollateral, LendingObligationLiquidity,
+    };
+    use crate::lending::reserve::{ReserveConfig, ReserveLiquidity};
+
+    fn mock_reserve(mint: Pubkey, price: u64) -> Reserve {
+        Reserve {
+            liquidity: ReserveLiquidity {
+                mint_pubkey: mint,
+                cumulative_borrow_rate_wads: Decimal::one(),
+                market_price: Decimal::from(price),
+                ..Default::default()
+            },
+            config: ReserveConfig {
+                loan_to_value_ratio: 75,
+                liquidation_threshold: 80,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn mock_obligation(
+        deposit_reserve: Pubkey,
+        deposited_amount: u64,
+        borrow_reserve: Pubkey,
+        borrowed_amount: u64,
+    ) -> LendingObligation {
+        LendingObligation {
+            deposits: vec![LendingObligationCollateral {
+                deposit_reserve,
+                deposited_amount,
+                market_value: Decimal::zero(),
+            }],
+            borrows: vec![LendingObligationLiquidity {
+                borrow_reserve,
+                cumulative_borrow_rate_wads: Decimal::one(),
+                borrowed_amount_wads: Decimal::from(borrowed_amount),
+                market_value: Decimal::zero(),
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn mock_market() -> (Pubkey, Pubkey, Pubkey, Pubkey, HashMap<Pubkey, Reserve>) {
+        let sol_mint = Pubkey::new_unique();
+        let usdc_mint = Pubkey::new_unique();
+        let sol_reserve = Pubkey::new_unique();
+        let usdc_reserve = Pubkey::new_unique();
+        let mut reserves = HashMap::new();
+        reserves.insert(sol_reserve, mock_reserve(sol_mint, 100));
+        reserves.insert(usdc_reserve, mock_reserve(usdc_mint, 1));
+        (sol_mint, usdc_mint, sol_reserve, usdc_reserve, reserves)
+    }
+
+    #[test]
+    fn test_liquidation_price_long() {
+        let (sol_mint, usdc_mint, sol_reserve, usdc_reserve, reserves) = mock_market();
+        // 10 sol deposited, 400 usdc borrowed, liquidated when 8 * price <= 400
+        let obligation = mock_obligation(sol_reserve, 10, usdc_reserve, 400);
+        let result = calculate_liquidation_price(&obligation, &reserves, sol_mint).unwrap();
+        assert_eq!(result.current_price, Decimal::from(100_u64));
+        assert_eq!(result.direction, LiquidationDirection::PriceFalls);
+        assert_eq!(result.liquidation_price, Some(Decimal::from(50_u64)));
+
+        // usdc is only borrowed, so it must rise to 2
+        let result = calculate_liquidation_price(&obligation, &reserves, usdc_mint).unwrap();
+        assert_eq!(result.direction, LiquidationDirection::PriceRises);
+        assert_eq!(result.liquidation_price, Some(Decimal::from(2_u64)));
+
+        let obligation = mock_obligation(sol_reserve, 10, usdc_reserve, 800);
+        let result = calculate_liquidation_price(&obligation, &reserves, sol_mint).unwrap();
+        assert_eq!(result.direction, LiquidationDirection::AlreadyUnhealthy);
+        assert_eq!(result.liquidation_price, None);
+    }
+
+    #[test]
+    fn test_liquidation_price_short() {
+        let (sol_mint, _, sol_reserve, usdc_reserve, reserves) = mock_market();
+        // 1000 usdc deposited, 4 sol borrowed, liquidated when 4 * price >= 800
+        let obligation = mock_obligation(usdc_reserve, 1_000, sol_reserve, 4);
+        let result = calculate_liquidation_price(&obligation, &reserves, sol_mint).unwrap();
+        assert_eq!(result.direction, LiquidationDirection::PriceRises);
+        assert_eq!(result.liquidation_price, Some(Decimal::from(200_u64)));
+
+        // the same asset deposited and borrowed can never be liquidated by its price alone
+        let obligation = mock_obligation(sol_reserve, 10, sol_reserve, 1);
+        let result = calculate_liquidation_price(&obligation, &reserves, sol_mint).unwrap();
+        assert_eq!(result.direction, LiquidationDirection::Never);
+
+        assert!(calculate_liquidation_price(&obligation, &reserves, Pubkey::new_unique()).is_err());
+    }
+
+    #[test]
+    fn test_liquidation_surface() {
+        let (sol_mint, usdc_mint, sol_reserve, usdc_reserve, reserves) = mock_market();
+        let obligation = mock_obligation(sol_reserve, 10, usdc_reserve, 400);
+        let x_prices = price_range(Decimal::from(50_u64), Decimal::from(150_u64), 3).unwrap();
+        assert_eq!(
+            x_prices,
+            vec![
+                Decimal::from(50_u64),
+                Decimal::from(100_u64),
+                Decimal::from(150_u64)
+            ]
+        );
+        assert_eq!(
+            price_range(Decimal::from(50_u64), Decimal::from(150_u64), 1),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(
+            price_range(Decimal::from(150_u64), Decimal::from(50_u64), 3),
+            Err(ProgramError::InvalidArgument)
+        );
+        let y_prices = vec![Decimal::one(), Decimal::from(2_u64)];
+        let surface = calculate_liquidation_surface(
+            &obligation,
+            &reserves,
+            sol_mint,
+            &x_prices,
+            usdc_mint,
+            &y_prices,
+        )
+        .unwrap();
+        assert_eq!(surface.health.len(), 3);
+        assert_eq!(
+            surface.health[0],
+            vec![Decimal::one(), Decimal::from_percent(50)]
+        );
+        assert_eq!(surface.health[1][0], Decimal::from(2_u64));
+        // at 100 sol, usdc must rise to 2, and at 150 sol to 3
+        assert_eq!(
+            surface.y_liquidation_prices[1].liquidation_price,
+            Some(Decimal::from(2_u64))
+        );
+        assert_eq!(
+            surface.y_liquidation_prices[2].liquidation_price,
+            Some(Decimal::from(3_u64))
+        );
+        assert_eq!(
+            surface.y_liquidation_prices[0].direction,
+            LiquidationDirection::AlreadyUnhealthy
+        );
+    }
+}
@@ -8,10 +8,15 @@
 
 pub mod error;
 pub mod last_update;
+pub mod lending_market;
 pub mod lending_obligation;
 pub mod leverage_farm;
+pub mod liquidation_price;
 pub mod obligation;
+pub mod oracle_refresh;
+pub mod rates;
 pub mod reserve;
+pub mod simulation;
 
 use crate::math::{common::WAD, decimal::Decimal};
 
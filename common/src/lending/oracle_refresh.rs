@@ -0,0 +1,292 @@
+//! off-chain refresh of reserves and lending obligations against fresh oracle prices,
+//! rather than the `market_price` cached by the last `RefreshReserve` instruction.
+//!
+//! interest is accrued up to the given slot, allowing the present health of an obligation
+//! to be calculated without sending any transactions
+
+use super::error::LendingError;
+use super::lending_obligation::{pseudo_refresh_lending_obligation, LendingObligation};
+use super::reserve::Reserve;
+use crate::math::decimal::Decimal;
+use crate::pyth::PriceValidationPolicy;
+use crate::traits::oracle::Oracle;
+use anchor_lang::solana_program::{clock::Slot, msg, program_error::ProgramError, pubkey::Pubkey};
+use std::collections::HashMap;
+
+impl Reserve {
+    /// accrues interest up to `current_slot` and sets the market price, mirroring
+    /// the `RefreshReserve` instruction
+    pub fn pseudo_refresh(
+        &mut self,
+        market_price: Decimal,
+        current_slot: Slot,
+    ) -> Result<(), ProgramError> {
+        self.accrue_interest(current_slot)?;
+        self.liquidity.market_price = market_price;
+        self.last_update.update_slot(current_slot);
+        Ok(())
+    }
+
+    /// returns the price from `price_overrides` keyed by the liquidity mint if present,
+    /// otherwise the validated price of the oracle keyed by the reserve's oracle pubkey
+    pub fn fresh_market_price<O: Oracle>(
+        &self,
+        oracles: &HashMap<Pubkey, O>,
+        price_overrides: &HashMap<Pubkey, Decimal>,
+        current_slot: Slot,
+        policy: &PriceValidationPolicy,
+    ) -> Result<Decimal, ProgramError> {
+        if let Some(price) = price_overrides.get(&self.liquidity.mint_pubkey) {
+            return Ok(*price);
+        }
+        let oracle = match oracles.get(&self.liquidity.oracle_pubkey) {
+            Some(oracle) => oracle,
+            None => {
+                msg!(
+                    "failed to find oracle {} for mint {}",
+                    self.liquidity.oracle_pubkey,
+                    self.liquidity.mint_pubkey
+                );
+                return Err(LendingError::InvalidOracleConfig.into());
+            }
+        };
+        oracle.validated_price(current_slot, policy).map_err(|err| {
+            msg!(
+                "invalid price from oracle {}: {}",
+                self.liquidity.oracle_pubkey,
+                err
+            );
+            LendingError::InvalidOracleConfig.into()
+        })
+    }
+}
+
+/// performs an off-chain refresh of the lending obligation using fresh prices, after accruing
+/// the interest of each reserve the obligation uses up to `current_slot`.
+///
+/// prices are taken from `price_overrides` keyed by liquidity mint, falling back to `oracles`
+/// keyed by the reserve's oracle pubkey. the cached reserve market price is never used.
+///
+/// returns the refreshed copies of the reserves used by the obligation
+pub fn refresh_lending_obligation_with_oracles<O: Oracle>(
+    obligation: &mut LendingObligation,
+    reserves: &HashMap<Pubkey, Reserve>,
+    oracles: &HashMap<Pubkey, O>,
+    price_overrides: &HashMap<Pubkey, Decimal>,
+    current_slot: Slot,
+    policy: &PriceValidationPolicy,
+) -> Result<HashMap<Pubkey, Reserve>, ProgramError> {
+    let reserve_keys = obligation
+        .deposits
+        .iter()
+        .map(|collateral| collateral.deposit_reserve)
+        .chain(
+            obligation
+                .borrows
+                .iter()
+                .map(|liquidity| liquidity.borrow_reserve),
+        );
+    let mut refreshed_reserves = HashMap::new();
+    for reserve_key in reserve_keys {
+        if refreshed_reserves.contains_key(&reserve_key) {
+            continue;
+        }
+        let mut reserve = match reserves.get(&reserve_key) {
+            Some(reserve) => reserve.clone(),
+            None => {
+                msg!("failed to find reserve {}", reserve_key);
+                return Err(ProgramError::InvalidAccountData);
+            }
+        };
+        let market_price =
+            reserve.fresh_market_price(oracles, price_overrides, current_slot, policy)?;
+        reserve.pseudo_refresh(market_price, current_slot)?;
+        refreshed_reserves.insert(reserve_key, reserve);
+    }
+    pseudo_refresh_lending_obligation(obligation, &refreshed_reserves)?;
+    obligation.last_update.update_slot(current_slot);
+    Ok(refreshed_reserves)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lending::last_update::LastUpdate;
+    use crate::lending::lending_obligation::{
+        LendingObligationCollateral, LendingObligationLiquidity,
+    };
+    use crate::lending::reserve::{ReserveCollateral, ReserveConfig, ReserveLiquidity};
+    use crate::pyth::{AccountType, Price, MAGIC, VERSION};
+    use crate::traits::oracle::MockOracle;
+
+    fn mock_reserve(mint: Pubkey, oracle: Pubkey) -> Reserve {
+        Reserve {
+            last_update: LastUpdate {
+                slot: 100,
+                stale: true,
+            },
+            liquidity: ReserveLiquidity {
+                mint_pubkey: mint,
+                oracle_pubkey: oracle,
+                available_amount: 500,
+                borrowed_amount_wads: Decimal::from(500_u64),
+                cumulative_borrow_rate_wads: Decimal::one(),
+                // stale cached price which must be ignored
+                market_price: Decimal::from(1_000_u64),
+                ..Default::default()
+            },
+            collateral: ReserveCollateral {
+                mint_total_supply: 1_000,
+                ..Default::default()
+            },
+            config: ReserveConfig {
+                optimal_utilization_rate: 50,
+                degen_utilization_rate: 90,
+                loan_to_value_ratio: 50,
+                liquidation_threshold: 60,
+                optimal_borrow_rate: 10,
+                degen_borrow_rate: 50,
+                max_borrow_rate: 250,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn mock_obligation(deposit_reserve: Pubkey, borrow_reserve: Pubkey) -> LendingObligation {
+        let mut obligation = LendingObligation::default();
+        obligation.deposits.push(LendingObligationCollateral {
+            deposit_reserve,
+            deposited_amount: 100,
+            ..Default::default()
+        });
+        obligation.borrows.push(LendingObligationLiquidity {
+            borrow_reserve,
+            cumulative_borrow_rate_wads: Decimal::one(),
+            borrowed_amount_wads: Decimal::from(50_u64),
+            ..Default::default()
+        });
+        obligation
+    }
+
+    #[test]
+    fn test_refresh_with_mock_oracles() {
+        let (deposit_key, borrow_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (deposit_oracle, borrow_oracle) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut reserves = HashMap::new();
+        let mut deposit_reserve = mock_reserve(Pubkey::new_unique(), deposit_oracle);
+        // no borrows, so the collateral exchange rate is unaffected by interest
+        deposit_reserve.liquidity.borrowed_amount_wads = Decimal::zero();
+        deposit_reserve.liquidity.available_amount = 1_000;
+        reserves.insert(deposit_key, deposit_reserve);
+        reserves.insert(
+            borrow_key,
+            mock_reserve(Pubkey::new_unique(), borrow_oracle),
+        );
+        let mut oracles = HashMap::new();
+        oracles.insert(deposit_oracle, MockOracle::new(Decimal::from(2_u64), 200));
+        oracles.insert(borrow_oracle, MockOracle::new(Decimal::one(), 200));
+        let policy = PriceValidationPolicy::default();
+
+        let mut obligation = mock_obligation(deposit_key, borrow_key);
+        let refreshed = refresh_lending_obligation_with_oracles(
+            &mut obligation,
+            &reserves,
+            &oracles,
+            &HashMap::new(),
+            200,
+            &policy,
+        )
+        .unwrap();
+        assert_eq!(refreshed.len(), 2);
+        // 100 collateral at an exchange rate of 1 and a price of 2
+        assert_eq!(obligation.deposited_value, Decimal::from(200_u64));
+        assert_eq!(obligation.unhealthy_borrow_value, Decimal::from(120_u64));
+        // interest accrued over 100 slots
+        let borrow_reserve = refreshed.get(&borrow_key).unwrap();
+        assert_eq!(borrow_reserve.last_update.slot, 200);
+        assert!(!borrow_reserve.last_update.stale);
+        assert!(borrow_reserve.liquidity.cumulative_borrow_rate_wads > Decimal::one());
+        assert!(obligation.borrowed_value > Decimal::from(50_u64));
+        assert_eq!(obligation.last_update.slot, 200);
+        // the input reserves are left untouched
+        assert_eq!(reserves.get(&borrow_key).unwrap().last_update.slot, 100);
+
+        // stale oracle
+        let mut obligation = mock_obligation(deposit_key, borrow_key);
+        assert!(refresh_lending_obligation_with_oracles(
+            &mut obligation,
+            &reserves,
+            &oracles,
+            &HashMap::new(),
+            200 + policy.max_slot_age + 1,
+            &policy,
+        )
+        .is_err());
+
+        // missing oracle
+        oracles.remove(&borrow_oracle);
+        assert!(refresh_lending_obligation_with_oracles(
+            &mut obligation,
+            &reserves,
+            &oracles,
+            &HashMap::new(),
+            200,
+            &policy,
+        )
+        .is_err());
+
+        // overrides take precedence over, and fill in for, oracles
+        let mut price_overrides = HashMap::new();
+        for reserve in reserves.values() {
+            price_overrides.insert(reserve.liquidity.mint_pubkey, Decimal::from(3_u64));
+        }
+        refresh_lending_obligation_with_oracles(
+            &mut obligation,
+            &reserves,
+            &oracles,
+            &price_overrides,
+            200,
+            &policy,
+        )
+        .unwrap();
+        assert_eq!(obligation.deposited_value, Decimal::from(300_u64));
+    }
+
+    #[test]
+    fn test_refresh_with_pyth_prices() {
+        let (reserve_key, oracle_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut reserves = HashMap::new();
+        reserves.insert(reserve_key, mock_reserve(Pubkey::new_unique(), oracle_key));
+        let mut pyth_price = Price {
+            magic: MAGIC,
+            ver: VERSION,
+            atype: AccountType::Price as u32,
+            expo: -2,
+            ..Default::default()
+        };
+        pyth_price.agg.price = 150;
+        pyth_price.agg.conf = 1;
+        pyth_price.agg.pub_slot = 100;
+        let mut oracles = HashMap::new();
+        oracles.insert(oracle_key, pyth_price);
+
+        let mut obligation = mock_obligation(reserve_key, reserve_key);
+        let refreshed = refresh_lending_obligation_with_oracles(
+            &mut obligation,
+            &reserves,
+            &oracles,
+            &HashMap::new(),
+            100,
+            &PriceValidationPolicy::default(),
+        )
+        .unwrap();
+        assert_eq!(refreshed.len(), 1);
+        assert_eq!(
+            refreshed.get(&reserve_key).unwrap().liquidity.market_price,
+            Decimal::from_percent(150)
+        );
+        assert_eq!(obligation.deposited_value, Decimal::from(150_u64));
+        assert_eq!(obligation.borrowed_value, Decimal::from(75_u64));
+    }
+}
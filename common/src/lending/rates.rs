@@ -0,0 +1,215 @@
+//! off-chain interest rate projections for a reserve, such as supply and borrow apy,
+//! along with "what-if" rates after a hypothetical deposit, withdrawal, borrow or repayment
+
+use super::reserve::Reserve;
+use super::SLOTS_PER_YEAR;
+use crate::math::{
+    common::{TryAdd, TryDiv, TryMul, TrySub},
+    decimal::Decimal,
+    rate::Rate,
+};
+use anchor_lang::solana_program::program_error::ProgramError;
+
+/// Interest rates of a reserve at a given utilization rate, all rates are annualized
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ReserveRates {
+    /// Ratio of borrowed liquidity to total supply
+    pub utilization_rate: Rate,
+    /// Borrow rate before compounding
+    pub borrow_apr: Rate,
+    /// Borrow rate compounded every slot
+    pub borrow_apy: Rate,
+    /// Rate earned by depositors before compounding, net of the protocol take rate
+    pub supply_apr: Rate,
+    /// Rate earned by depositors compounded every slot, net of the protocol take rate
+    pub supply_apy: Rate,
+    /// Portion of the interest paid by borrowers that accrues to the platform
+    pub protocol_take_rate: Rate,
+    /// Interest accruing to the platform, as a portion of total supply
+    pub protocol_apr: Rate,
+    /// Origination fee charged on borrows, see `ReserveFees::borrow_fee_wad`
+    pub borrow_fee_rate: Rate,
+    /// Portion of the origination and flash loan fees that go to the host
+    pub host_fee_rate: Rate,
+}
+
+/// A hypothetical action used to project the rates of a reserve
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReserveAction {
+    /// Deposit the given amount of liquidity
+    Deposit(u64),
+    /// Withdraw the given amount of liquidity
+    Withdraw(u64),
+    /// Borrow the given amount of liquidity, exclusive of fees
+    Borrow(u64),
+    /// Repay the given amount of liquidity
+    Repay(u64),
+}
+
+impl Reserve {
+    /// Calculate the rates of the reserve at its current utilization rate
+    pub fn current_rates(&self) -> Result<ReserveRates, ProgramError> {
+        self.rates_at_utilization(self.liquidity.utilization_rate()?)
+    }
+
+    /// Calculate the rates the reserve would have at the given utilization rate
+    pub fn rates_at_utilization(
+        &self,
+        utilization_rate: Rate,
+    ) -> Result<ReserveRates, ProgramError> {
+        let borrow_apr = self.borrow_rate_at_utilization(utilization_rate)?;
+        let protocol_take_rate = Rate::from_percent(self.liquidity.platform_fees);
+        let gross_supply_apr = borrow_apr.try_mul(utilization_rate)?;
+        let protocol_apr = gross_supply_apr.try_mul(protocol_take_rate)?;
+        let supply_apr = gross_supply_apr.try_sub(protocol_apr)?;
+        Ok(ReserveRates {
+            utilization_rate,
+            borrow_apr,
+            borrow_apy: apr_to_apy(borrow_apr)?,
+            supply_apr,
+            supply_apy: apr_to_apy(supply_apr)?,
+            protocol_take_rate,
+            protocol_apr,
+            borrow_fee_rate: Rate::from_scaled_val(self.config.fees.borrow_fee_wad),
+            host_fee_rate: Rate::from_percent(self.config.fees.host_fee_percentage),
+        })
+    }
+
+    /// Calculate the utilization rate the reserve would have after the given action
+    pub fn utilization_rate_after(&self, action: ReserveAction) -> Result<Rate, ProgramError> {
+        let mut liquidity = self.liquidity.clone();
+        match action {
+            ReserveAction::Deposit(amount) => liquidity.deposit(amount)?,
+            ReserveAction::Withdraw(amount) => liquidity.withdraw(amount)?,
+            ReserveAction::Borrow(amount) => {
+                liquidity.borrow(self.margin_calculate_borrow(amount)?.borrow_amount)?
+            }
+            ReserveAction::Repay(amount) => {
+                let repay = self.calculate_repay(amount, liquidity.borrowed_amount_wads)?;
+                liquidity.repay(repay.repay_amount, repay.settle_amount)?
+            }
+        }
+        liquidity.utilization_rate()
+    }
+
+    /// Calculate the rates the reserve would have after the given action
+    pub fn rates_after(&self, action: ReserveAction) -> Result<ReserveRates, ProgramError> {
+        self.rates_at_utilization(self.utilization_rate_after(action)?)
+    }
+}
+
+/// Compounds an annual rate every slot, returning the effective annual rate
+pub fn apr_to_apy(apr: Rate) -> Result<Rate, ProgramError> {
+    Rate::one()
+        .try_add(apr.try_div(SLOTS_PER_YEAR)?)?
+        .try_pow(SLOTS_PER_YEAR)?
+        .try_sub(Rate::one())
+}
+
+/// Converts a rate into a percentage, ie: 0.05 -> 5.0, intended for display purposes
+pub fn rate_to_percent(rate: Rate) -> Result<Decimal, ProgramError> {
+    Decimal::from(rate).try_mul(100_u64)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lending::reserve::{ReserveConfig, ReserveLiquidity};
+
+    fn mock_reserve() -> Reserve {
+        Reserve {
+            liquidity: ReserveLiquidity {
+                available_amount: 500,
+                borrowed_amount_wads: Decimal::from(500_u64),
+                platform_fees: 10,
+                ..Default::default()
+            },
+            config: ReserveConfig {
+                optimal_utilization_rate: 80,
+                degen_utilization_rate: 90,
+                min_borrow_rate: 0,
+                optimal_borrow_rate: 10,
+                degen_borrow_rate: 30,
+                max_borrow_rate: 100,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn assert_close(left: Rate, right: Rate) {
+        let diff = if left > right {
+            left.try_sub(right).unwrap()
+        } else {
+            right.try_sub(left).unwrap()
+        };
+        assert!(
+            diff < Rate::from_scaled_val(1_000_000_000),
+            "{} != {}",
+            left,
+            right
+        );
+    }
+
+    #[test]
+    fn test_current_rates() {
+        let reserve = mock_reserve();
+        let rates = reserve.current_rates().unwrap();
+        assert_eq!(rates.utilization_rate, Rate::from_percent(50));
+        // 50 / 80 * 10%
+        assert_eq!(
+            rates.borrow_apr,
+            Rate::from_scaled_val(62_500_000_000_000_000)
+        );
+        // 6.25% * 50% * 90%
+        assert_eq!(
+            rates.supply_apr,
+            Rate::from_scaled_val(28_125_000_000_000_000)
+        );
+        assert_eq!(
+            rates.protocol_apr,
+            Rate::from_scaled_val(3_125_000_000_000_000)
+        );
+        // (1 + 0.0625 / SLOTS_PER_YEAR)^SLOTS_PER_YEAR - 1
+        assert_close(
+            rates.borrow_apy,
+            Rate::from_scaled_val(64_494_458_884_895_662),
+        );
+        // (1 + 0.028125 / SLOTS_PER_YEAR)^SLOTS_PER_YEAR - 1
+        assert_close(
+            rates.supply_apy,
+            Rate::from_scaled_val(28_524_241_910_204_179),
+        );
+        assert!(rates.borrow_apy > rates.borrow_apr);
+    }
+
+    #[test]
+    fn test_rates_after() {
+        let reserve = mock_reserve();
+        let rates = reserve.rates_after(ReserveAction::Borrow(300)).unwrap();
+        assert_eq!(rates.utilization_rate, Rate::from_percent(80));
+        assert_eq!(rates.borrow_apr, Rate::from_percent(10));
+
+        let rates = reserve.rates_after(ReserveAction::Deposit(1_500)).unwrap();
+        assert_eq!(rates.utilization_rate, Rate::from_percent(20));
+
+        let rates = reserve.rates_after(ReserveAction::Repay(u64::MAX)).unwrap();
+        assert_eq!(rates.utilization_rate, Rate::zero());
+        assert_eq!(rates.supply_apy, Rate::zero());
+
+        assert!(reserve.rates_after(ReserveAction::Withdraw(501)).is_err());
+        // the current rates are left untouched
+        assert_eq!(
+            reserve.current_rates().unwrap().utilization_rate,
+            Rate::from_percent(50)
+        );
+    }
+
+    #[test]
+    fn test_rate_to_percent() {
+        assert_eq!(
+            rate_to_percent(Rate::from_percent(5)).unwrap(),
+            Decimal::from(5_u64)
+        );
+    }
+}
@@ -1,5 +1,8 @@
 use super::error::LendingError;
 use super::last_update::LastUpdate;
+use super::lending_obligation::{
+    LendingObligation, LendingObligationCollateral, LendingObligationLiquidity,
+};
 use super::obligation::{Obligation, ObligationCollateral, ObligationLiquidity};
 use super::*;
 use crate::math::{
@@ -100,7 +103,11 @@ impl Reserve {
 
     /// Calculate the current borrow rate
     pub fn current_borrow_rate(&self) -> Result<Rate, ProgramError> {
-        let utilization_rate = self.liquidity.utilization_rate()?;
+        self.borrow_rate_at_utilization(self.liquidity.utilization_rate()?)
+    }
+
+    /// Calculate the borrow rate the reserve would have at the given utilization rate
+    pub fn borrow_rate_at_utilization(&self, utilization_rate: Rate) -> Result<Rate, ProgramError> {
         let optimal_utilization_rate = Rate::from_percent(self.config.optimal_utilization_rate);
         let degen_utilization_rate = Rate::from_percent(self.config.degen_utilization_rate);
 
@@ -278,13 +285,50 @@ impl Reserve {
         obligation: &Obligation,
         liquidity: &ObligationLiquidity,
         collateral: &ObligationCollateral,
+    ) -> Result<CalculateLiquidationResult, ProgramError> {
+        self.liquidation_amounts(
+            amount_to_liquidate,
+            liquidity.borrowed_amount_wads,
+            liquidity.market_value,
+            collateral.market_value,
+            collateral.deposited_amount,
+            || obligation.max_liquidation_amount(liquidity),
+        )
+    }
+
+    /// Liquidate some or all of an unhealthy lending obligation
+    pub fn calculate_lending_obligation_liquidation(
+        &self,
+        amount_to_liquidate: u64,
+        obligation: &LendingObligation,
+        liquidity: &LendingObligationLiquidity,
+        collateral: &LendingObligationCollateral,
+    ) -> Result<CalculateLiquidationResult, ProgramError> {
+        self.liquidation_amounts(
+            amount_to_liquidate,
+            liquidity.borrowed_amount_wads,
+            liquidity.market_value,
+            collateral.market_value,
+            collateral.deposited_amount,
+            || obligation.max_liquidation_amount(liquidity),
+        )
+    }
+
+    fn liquidation_amounts(
+        &self,
+        amount_to_liquidate: u64,
+        borrowed_amount_wads: Decimal,
+        liquidity_market_value: Decimal,
+        collateral_market_value: Decimal,
+        deposited_amount: u64,
+        max_liquidation_amount: impl FnOnce() -> Result<Decimal, ProgramError>,
     ) -> Result<CalculateLiquidationResult, ProgramError> {
         let bonus_rate = Rate::from_percent(self.config.liquidation_bonus).try_add(Rate::one())?;
 
         let max_amount = if amount_to_liquidate == u64::MAX {
-            liquidity.borrowed_amount_wads
+            borrowed_amount_wads
         } else {
-            Decimal::from(amount_to_liquidate).min(liquidity.borrowed_amount_wads)
+            Decimal::from(amount_to_liquidate).min(borrowed_amount_wads)
         };
 
         let settle_amount;
@@ -292,57 +336,54 @@ impl Reserve {
         let withdraw_amount;
 
         // Close out obligations that are too small to liquidate normally
-        if liquidity.borrowed_amount_wads < LIQUIDATION_CLOSE_AMOUNT.into() {
+        if borrowed_amount_wads < LIQUIDATION_CLOSE_AMOUNT.into() {
             // settle_amount is fixed, calculate withdraw_amount and repay_amount
-            settle_amount = liquidity.borrowed_amount_wads;
+            settle_amount = borrowed_amount_wads;
 
-            let liquidation_value = liquidity.market_value.try_mul(bonus_rate)?;
-            match liquidation_value.cmp(&collateral.market_value) {
+            let liquidation_value = liquidity_market_value.try_mul(bonus_rate)?;
+            match liquidation_value.cmp(&collateral_market_value) {
                 Ordering::Greater => {
-                    let repay_pct = collateral.market_value.try_div(liquidation_value)?;
+                    let repay_pct = collateral_market_value.try_div(liquidation_value)?;
                     repay_amount = max_amount.try_mul(repay_pct)?.try_ceil_u64()?;
-                    withdraw_amount = collateral.deposited_amount;
+                    withdraw_amount = deposited_amount;
                 }
                 Ordering::Equal => {
                     repay_amount = max_amount.try_ceil_u64()?;
-                    withdraw_amount = collateral.deposited_amount;
+                    withdraw_amount = deposited_amount;
                 }
                 Ordering::Less => {
-                    let withdraw_pct = liquidation_value.try_div(collateral.market_value)?;
+                    let withdraw_pct = liquidation_value.try_div(collateral_market_value)?;
                     repay_amount = max_amount.try_floor_u64()?;
-                    withdraw_amount = Decimal::from(collateral.deposited_amount)
+                    withdraw_amount = Decimal::from(deposited_amount)
                         .try_mul(withdraw_pct)?
                         .try_floor_u64()?;
                 }
             }
         } else {
             // calculate settle_amount and withdraw_amount, repay_amount is settle_amount rounded
-            let liquidation_amount = obligation
-                .max_liquidation_amount(liquidity)?
-                .min(max_amount);
-            let liquidation_pct = liquidation_amount.try_div(liquidity.borrowed_amount_wads)?;
-            let liquidation_value = liquidity
-                .market_value
+            let liquidation_amount = max_liquidation_amount()?.min(max_amount);
+            let liquidation_pct = liquidation_amount.try_div(borrowed_amount_wads)?;
+            let liquidation_value = liquidity_market_value
                 .try_mul(liquidation_pct)?
                 .try_mul(bonus_rate)?;
 
-            match liquidation_value.cmp(&collateral.market_value) {
+            match liquidation_value.cmp(&collateral_market_value) {
                 Ordering::Greater => {
-                    let repay_pct = collateral.market_value.try_div(liquidation_value)?;
+                    let repay_pct = collateral_market_value.try_div(liquidation_value)?;
                     settle_amount = liquidation_amount.try_mul(repay_pct)?;
                     repay_amount = settle_amount.try_ceil_u64()?;
-                    withdraw_amount = collateral.deposited_amount;
+                    withdraw_amount = deposited_amount;
                 }
                 Ordering::Equal => {
                     settle_amount = liquidation_amount;
                     repay_amount = settle_amount.try_ceil_u64()?;
-                    withdraw_amount = collateral.deposited_amount;
+                    withdraw_amount = deposited_amount;
                 }
                 Ordering::Less => {
-                    let withdraw_pct = liquidation_value.try_div(collateral.market_value)?;
+                    let withdraw_pct = liquidation_value.try_div(collateral_market_value)?;
                     settle_amount = liquidation_amount;
                     repay_amount = settle_amount.try_floor_u64()?;
-                    withdraw_amount = Decimal::from(collateral.deposited_amount)
+                    withdraw_amount = Decimal::from(deposited_amount)
                         .try_mul(withdraw_pct)?
                         .try_floor_u64()?;
                 }
@@ -554,7 +595,7 @@ impl ReserveLiquidity {
     }
 
     /// Compound current borrow rate over elapsed slots
-    fn compound_interest(
+    pub(crate) fn compound_interest(
         &mut self,
         current_borrow_rate: Rate,
         slots_elapsed: u64,
@@ -0,0 +1,459 @@
+//! off-chain simulation of reserves and lending obligations forward in time, used to
+//! project debt growth and obligation health at current, constant or scripted utilization.
+//!
+//! market prices are held constant for the duration of a simulation
+
+use super::lending_obligation::{pseudo_refresh_lending_obligation, LendingObligation};
+use super::reserve::Reserve;
+use super::SLOTS_PER_YEAR;
+use crate::math::{decimal::Decimal, rate::Rate};
+use anchor_lang::solana_program::{
+    clock::SECONDS_PER_DAY, msg, program_error::ProgramError, pubkey::Pubkey,
+};
+use std::collections::HashMap;
+
+/// The maximum number of steps a simulation can be split into, which bounds the
+/// number of projections a simulation returns
+pub const MAX_SIMULATION_STEPS: u64 = 100_000;
+
+/// How far into the future a simulation runs
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SimulationHorizon {
+    Slots(u64),
+    Seconds(u64),
+}
+
+impl SimulationHorizon {
+    /// returns the number of slots covered by the horizon, using the same
+    /// slot time that `SLOTS_PER_YEAR` is derived from
+    pub fn slots(&self) -> u64 {
+        match self {
+            Self::Slots(slots) => *slots,
+            Self::Seconds(seconds) => {
+                (*seconds as u128 * SLOTS_PER_YEAR as u128 / (SECONDS_PER_DAY as u128 * 365)) as u64
+            }
+        }
+    }
+}
+
+/// How the utilization rate, and thus the borrow rate, of a reserve evolves during a simulation
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum UtilizationSchedule {
+    /// utilization is derived from the reserve state, which grows as interest accrues
+    #[default]
+    Current,
+    /// utilization is held at the given rate
+    Constant(Rate),
+    /// pairs of (slot offset, utilization) sorted by slot offset. the most recent entry at or
+    /// before a slot offset is used, with the reserve state used before the first entry
+    Scripted(Vec<(u64, Rate)>),
+}
+
+impl UtilizationSchedule {
+    /// returns the utilization rate of the reserve at the given slot offset
+    pub fn utilization_at(
+        &self,
+        reserve: &Reserve,
+        slot_offset: u64,
+    ) -> Result<Rate, ProgramError> {
+        match self {
+            Self::Current => reserve.liquidity.utilization_rate(),
+            Self::Constant(utilization_rate) => Ok(*utilization_rate),
+            Self::Scripted(points) => match points
+                .iter()
+                .take_while(|(offset, _)| *offset <= slot_offset)
+                .last()
+            {
+                Some((_, utilization_rate)) => Ok(*utilization_rate),
+                None => reserve.liquidity.utilization_rate(),
+            },
+        }
+    }
+}
+
+/// Projected state of a reserve at a point in a simulation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReserveProjection {
+    /// Number of slots since the start of the simulation
+    pub slot_offset: u64,
+    /// Utilization rate used for the step ending at this point
+    pub utilization_rate: Rate,
+    /// Borrow rate used for the step ending at this point
+    pub borrow_rate: Rate,
+    /// Projected cumulative borrow rate
+    pub cumulative_borrow_rate_wads: Decimal,
+    /// Projected total borrowed liquidity, including interest
+    pub borrowed_amount_wads: Decimal,
+}
+
+/// Projected state of a single borrow of an obligation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BorrowProjection {
+    /// Reserve liquidity is borrowed from
+    pub borrow_reserve: Pubkey,
+    /// Projected cumulative borrow rate of the reserve
+    pub cumulative_borrow_rate_wads: Decimal,
+    /// Projected debt, including interest
+    pub borrowed_amount_wads: Decimal,
+    /// Projected debt value in quote currency
+    pub market_value: Decimal,
+}
+
+/// Projected state of a lending obligation at a point in a simulation
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ObligationProjection {
+    /// Number of slots since the start of the simulation
+    pub slot_offset: u64,
+    /// Projected state of each borrow
+    pub borrows: Vec<BorrowProjection>,
+    /// Projected market value of deposits
+    pub deposited_value: Decimal,
+    /// Projected market value of borrows
+    pub borrowed_value: Decimal,
+    /// Projected borrow value at the weighted average liquidation threshold
+    pub unhealthy_borrow_value: Decimal,
+    /// Ratio of unhealthy borrow value to borrowed value, below 1 the obligation can be liquidated
+    pub health: Decimal,
+}
+
+impl ObligationProjection {
+    /// returns true if the obligation can be liquidated at this point
+    pub fn is_unhealthy(&self) -> bool {
+        self.borrowed_value >= self.unhealthy_borrow_value && self.borrowed_value > Decimal::zero()
+    }
+}
+
+impl Reserve {
+    /// Accrue interest at the given borrow rate over the given number of slots,
+    /// regardless of the current utilization of the reserve
+    pub fn accrue_interest_at_rate(
+        &mut self,
+        borrow_rate: Rate,
+        slots: u64,
+    ) -> Result<(), ProgramError> {
+        if slots > 0 {
+            self.liquidity.compound_interest(borrow_rate, slots)?;
+            self.last_update.slot = self.last_update.slot.saturating_add(slots);
+        }
+        Ok(())
+    }
+
+    /// Project the reserve forward in time, returning its state at the start of the simulation
+    /// and after every `step_slots` slots, with the last step ending at the horizon
+    pub fn simulate(
+        &self,
+        horizon: SimulationHorizon,
+        step_slots: u64,
+        schedule: &UtilizationSchedule,
+    ) -> Result<Vec<ReserveProjection>, ProgramError> {
+        let mut reserve = self.clone();
+        let mut projections = Vec::new();
+        let mut slot_offset = 0;
+        let utilization_rate = schedule.utilization_at(&reserve, 0)?;
+        projections.push(ReserveProjection {
+            slot_offset,
+            utilization_rate,
+            borrow_rate: reserve.borrow_rate_at_utilization(utilization_rate)?,
+            cumulative_borrow_rate_wads: reserve.liquidity.cumulative_borrow_rate_wads,
+            borrowed_amount_wads: reserve.liquidity.borrowed_amount_wads,
+        });
+        for step in simulation_steps(horizon.slots(), step_slots, MAX_SIMULATION_STEPS)? {
+            let utilization_rate = schedule.utilization_at(&reserve, slot_offset)?;
+            let borrow_rate = reserve.borrow_rate_at_utilization(utilization_rate)?;
+            reserve.accrue_interest_at_rate(borrow_rate, step)?;
+            slot_offset += step;
+            projections.push(ReserveProjection {
+                slot_offset,
+                utilization_rate,
+                borrow_rate,
+                cumulative_borrow_rate_wads: reserve.liquidity.cumulative_borrow_rate_wads,
+                borrowed_amount_wads: reserve.liquidity.borrowed_amount_wads,
+            });
+        }
+        Ok(projections)
+    }
+}
+
+/// Project the obligation forward in time, accruing interest on every reserve the obligation
+/// deposits into or borrows from. reserves without an entry in `schedules` use
+/// `UtilizationSchedule::Current`.
+///
+/// returns the state of the obligation at the start of the simulation
+/// and after every `step_slots` slots, with the last step ending at the horizon
+pub fn simulate_lending_obligation(
+    obligation: &LendingObligation,
+    reserves: &HashMap<Pubkey, Reserve>,
+    schedules: &HashMap<Pubkey, UtilizationSchedule>,
+    horizon: SimulationHorizon,
+    step_slots: u64,
+) -> Result<Vec<ObligationProjection>, ProgramError> {
+    let mut obligation = obligation.clone();
+    let mut reserves = reserves.clone();
+    let mut reserve_keys: Vec<Pubkey> = obligation
+        .deposits
+        .iter()
+        .map(|collateral| collateral.deposit_reserve)
+        .chain(
+            obligation
+                .borrows
+                .iter()
+                .map(|liquidity| liquidity.borrow_reserve),
+        )
+        .collect();
+    // deposit and borrow reserves may be the same
+    reserve_keys.sort();
+    reserve_keys.dedup();
+    let default_schedule = UtilizationSchedule::default();
+
+    let mut projections = Vec::new();
+    let mut slot_offset = 0;
+    pseudo_refresh_lending_obligation(&mut obligation, &reserves)?;
+    projections.push(project_obligation(&obligation, slot_offset)?);
+    for step in simulation_steps(horizon.slots(), step_slots, MAX_SIMULATION_STEPS)? {
+        for reserve_key in reserve_keys.iter() {
+            let reserve = match reserves.get_mut(reserve_key) {
+                Some(reserve) => reserve,
+                None => {
+                    msg!("failed to find reserve {}", reserve_key);
+                    return Err(ProgramError::InvalidAccountData);
+                }
+            };
+            let schedule = schedules.get(reserve_key).unwrap_or(&default_schedule);
+            let utilization_rate = schedule.utilization_at(reserve, slot_offset)?;
+            let borrow_rate = reserve.borrow_rate_at_utilization(utilization_rate)?;
+            reserve.accrue_interest_at_rate(borrow_rate, step)?;
+        }
+        slot_offset += step;
+        pseudo_refresh_lending_obligation(&mut obligation, &reserves)?;
+        projections.push(project_obligation(&obligation, slot_offset)?);
+    }
+    Ok(projections)
+}
+
+/// returns the first projection at which the obligation can be liquidated
+pub fn first_unhealthy_projection(
+    projections: &[ObligationProjection],
+) -> Option<&ObligationProjection> {
+    projections
+        .iter()
+        .find(|projection| projection.is_unhealthy())
+}
+
+fn project_obligation(
+    obligation: &LendingObligation,
+    slot_offset: u64,
+) -> Result<ObligationProjection, ProgramError> {
+    Ok(ObligationProjection {
+        slot_offset,
+        borrows: obligation
+            .borrows
+            .iter()
+            .map(|liquidity| BorrowProjection {
+                borrow_reserve: liquidity.borrow_reserve,
+                cumulative_borrow_rate_wads: liquidity.cumulative_borrow_rate_wads,
+                borrowed_amount_wads: liquidity.borrowed_amount_wads,
+                market_value: liquidity.market_value,
+            })
+            .collect(),
+        deposited_value: obligation.deposited_value,
+        borrowed_value: obligation.borrowed_value,
+        unhealthy_borrow_value: obligation.unhealthy_borrow_value,
+        health: obligation.health()?,
+    })
+}
+
+/// splits `total_slots` into steps of `step_slots`, the last step may be shorter.
+///
+/// returns an error if the simulation would take more than `max_steps` steps
+fn simulation_steps(
+    total_slots: u64,
+    step_slots: u64,
+    max_steps: u64,
+) -> Result<impl Iterator<Item = u64>, ProgramError> {
+    if step_slots == 0 {
+        msg!("simulation step must be at least one slot");
+        return Err(ProgramError::InvalidArgument);
+    }
+    let full_steps = total_slots / step_slots;
+    let remainder = total_slots % step_slots;
+    if full_steps + u64::from(remainder > 0) > max_steps {
+        msg!("simulation cannot take more than {} steps", max_steps);
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok((0..full_steps)
+        .map(move |_| step_slots)
+        .chain(Some(remainder).filter(|remainder| *remainder > 0)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lending::lending_obligation::{
+        LendingObligationCollateral, LendingObligationLiquidity,
+    };
+    use crate::lending::reserve::{ReserveCollateral, ReserveConfig, ReserveLiquidity};
+
+    fn mock_reserve() -> Reserve {
+        Reserve {
+            liquidity: ReserveLiquidity {
+                available_amount: 500,
+                borrowed_amount_wads: Decimal::from(500_u64),
+                cumulative_borrow_rate_wads: Decimal::one(),
+                market_price: Decimal::one(),
+                ..Default::default()
+            },
+            collateral: ReserveCollateral {
+                mint_total_supply: 1_000,
+                ..Default::default()
+            },
+            config: ReserveConfig {
+                optimal_utilization_rate: 50,
+                degen_utilization_rate: 90,
+                loan_to_value_ratio: 50,
+                liquidation_threshold: 60,
+                min_borrow_rate: 0,
+                optimal_borrow_rate: 10,
+                degen_borrow_rate: 50,
+                max_borrow_rate: 250,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_simulation_horizon() {
+        assert_eq!(SimulationHorizon::Slots(10).slots(), 10);
+        assert_eq!(
+            SimulationHorizon::Seconds(SECONDS_PER_DAY * 365).slots(),
+            SLOTS_PER_YEAR
+        );
+    }
+
+    #[test]
+    fn test_simulate_reserve() {
+        let reserve = mock_reserve();
+        let projections = reserve
+            .simulate(
+                SimulationHorizon::Slots(25),
+                10,
+                &UtilizationSchedule::Current,
+            )
+            .unwrap();
+        let offsets: Vec<u64> = projections.iter().map(|p| p.slot_offset).collect();
+        assert_eq!(offsets, vec![0, 10, 20, 25]);
+        assert_eq!(projections[0].borrow_rate, Rate::from_percent(10));
+        assert!(
+            projections[3].cumulative_borrow_rate_wads > projections[0].cumulative_borrow_rate_wads
+        );
+        // the reserve itself is not modified
+        assert_eq!(reserve, mock_reserve());
+
+        let projections = reserve
+            .simulate(
+                SimulationHorizon::Slots(SLOTS_PER_YEAR),
+                SLOTS_PER_YEAR,
+                &UtilizationSchedule::Constant(Rate::zero()),
+            )
+            .unwrap();
+        assert_eq!(projections[1].borrow_rate, Rate::zero());
+        assert_eq!(projections[1].borrowed_amount_wads, Decimal::from(500_u64));
+
+        let schedule = UtilizationSchedule::Scripted(vec![
+            (10, Rate::from_percent(90)),
+            (20, Rate::from_percent(100)),
+        ]);
+        let projections = reserve
+            .simulate(SimulationHorizon::Slots(30), 10, &schedule)
+            .unwrap();
+        let rates: Vec<Rate> = projections.iter().map(|p| p.borrow_rate).collect();
+        assert_eq!(
+            rates,
+            vec![
+                Rate::from_percent(10),
+                Rate::from_percent(10),
+                Rate::from_percent(50),
+                Rate::from_percent(250)
+            ]
+        );
+        assert!(reserve
+            .simulate(SimulationHorizon::Slots(30), 0, &schedule)
+            .is_err());
+        assert!(reserve
+            .simulate(
+                SimulationHorizon::Slots(MAX_SIMULATION_STEPS + 1),
+                1,
+                &schedule
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_simulation_steps() {
+        let steps = |total_slots, step_slots, max_steps| {
+            simulation_steps(total_slots, step_slots, max_steps).map(|steps| steps.collect())
+        };
+        assert_eq!(steps(25, 10, 3), Ok(vec![10, 10, 5]));
+        assert_eq!(steps(30, 10, 3), Ok(vec![10, 10, 10]));
+        assert_eq!(steps(31, 10, 3), Err(ProgramError::InvalidArgument));
+        assert_eq!(steps(0, 10, 3), Ok(vec![]));
+        assert_eq!(steps(10, 0, 3), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_simulate_lending_obligation() {
+        let reserve_key = Pubkey::new_unique();
+        let mut reserves = HashMap::new();
+        reserves.insert(reserve_key, mock_reserve());
+        let obligation = LendingObligation {
+            deposits: vec![LendingObligationCollateral {
+                deposit_reserve: reserve_key,
+                deposited_amount: 100,
+                market_value: Decimal::zero(),
+            }],
+            borrows: vec![LendingObligationLiquidity {
+                borrow_reserve: reserve_key,
+                cumulative_borrow_rate_wads: Decimal::one(),
+                borrowed_amount_wads: Decimal::from(55_u64),
+                market_value: Decimal::zero(),
+            }],
+            ..Default::default()
+        };
+
+        let mut schedules = HashMap::new();
+        schedules.insert(reserve_key, UtilizationSchedule::Constant(Rate::one()));
+        let projections = simulate_lending_obligation(
+            &obligation,
+            &reserves,
+            &schedules,
+            SimulationHorizon::Slots(SLOTS_PER_YEAR),
+            SLOTS_PER_YEAR / 100,
+        )
+        .unwrap();
+        assert_eq!(projections.len(), 101);
+        assert_eq!(projections[0].borrowed_value, Decimal::from(55_u64));
+        assert_eq!(projections[0].unhealthy_borrow_value, Decimal::from(60_u64));
+        assert!(!projections[0].is_unhealthy());
+        assert!(projections[1].health < projections[0].health);
+        assert!(
+            projections[1].borrows[0].cumulative_borrow_rate_wads
+                > projections[0].borrows[0].cumulative_borrow_rate_wads
+        );
+
+        // debt grows at 250%, and collateral at roughly 125%, so the obligation
+        // becomes unhealthy after ~0.07 years, which is first observed at the next step
+        let unhealthy = first_unhealthy_projection(&projections).unwrap();
+        assert_eq!(unhealthy.slot_offset, SLOTS_PER_YEAR / 100 * 8);
+
+        // at the current 50% utilization the obligation stays healthy for the year
+        let projections = simulate_lending_obligation(
+            &obligation,
+            &reserves,
+            &HashMap::new(),
+            SimulationHorizon::Slots(SLOTS_PER_YEAR),
+            SLOTS_PER_YEAR / 100,
+        )
+        .unwrap();
+        assert!(first_unhealthy_projection(&projections).is_none());
+    }
+}
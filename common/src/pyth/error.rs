@@ -0,0 +1,40 @@
+//! Error types
+
+use anchor_lang::solana_program::{decode_error::DecodeError, program_error::ProgramError};
+use num_derive::FromPrimitive;
+use thiserror::Error;
+
+/// Errors that may be returned when loading and validating pyth price accounts
+#[derive(Clone, Debug, Eq, Error, FromPrimitive, PartialEq)]
+pub enum PythError {
+    #[error("Price account data is too short or misaligned")]
+    InvalidAccountData,
+    #[error("Price account has an invalid magic number, version or account type")]
+    InvalidAccountHeader,
+    #[error("Price account is not of type Price")]
+    InvalidPriceType,
+    #[error("Price status is not trading")]
+    PriceNotTrading,
+    #[error("Price has not been updated within the maximum slot age")]
+    StalePrice,
+    #[error("Price is zero or negative")]
+    NonPositivePrice,
+    #[error("Price confidence interval exceeds the maximum confidence ratio")]
+    ConfidenceTooWide,
+    #[error("Price exponent is positive")]
+    PositiveExponent,
+    #[error("Math operation overflow")]
+    MathOverflow,
+}
+
+impl From<PythError> for ProgramError {
+    fn from(e: PythError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
+
+impl<T> DecodeError<T> for PythError {
+    fn type_of() -> &'static str {
+        "Pyth Error"
+    }
+}
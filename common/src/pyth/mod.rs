@@ -2,9 +2,15 @@
 //! this should in theory work with any pyth v2 price account out there, however it is only tested
 //! with the accounts that tulip publishes prices for via our own price feed program.
 
-use crate::math::{common::TryDiv, decimal::Decimal};
+pub mod error;
+
+use crate::math::{
+    common::{TryDiv, TryMul},
+    decimal::Decimal,
+};
 use anchor_lang::prelude::ProgramError;
-use bytemuck::{cast_slice, from_bytes, try_cast_slice, Pod, PodCastError, Zeroable};
+use bytemuck::{cast_slice, try_cast_slice, try_from_bytes, Pod, PodCastError, Zeroable};
+use error::PythError;
 use std::mem::size_of;
 
 /// after this many slots consider a price update as being stale and thus invalid
@@ -165,9 +171,8 @@ unsafe impl Pod for Product {}
 
 pub fn load<T: Pod>(data: &[u8]) -> Result<&T, PodCastError> {
     let size = size_of::<T>();
-    Ok(from_bytes(cast_slice::<u8, u8>(try_cast_slice(
-        &data[0..size],
-    )?)))
+    let data = data.get(0..size).ok_or(PodCastError::SizeMismatch)?;
+    try_from_bytes(cast_slice::<u8, u8>(try_cast_slice(data)?))
 }
 
 pub fn load_pyth_price(pyth_price_account_data: &[u8]) -> Result<Decimal, ProgramError> {
@@ -188,7 +193,7 @@ pub fn load_pyth_price(pyth_price_account_data: &[u8]) -> Result<Decimal, Progra
 ///
 /// this is only intended for use with the pyth price feeds published by
 /// tulip, and should be used cautiously as inappropriate usage will result
-/// in loss of money
+/// in loss of money. prefer `load_validated_pyth_price` where possible.
 pub fn parse_pyth_price(pyth_price: &Price) -> Result<Decimal, ProgramError> {
     if pyth_price.ptype as u32 != PriceType::Price as u32 {
         return Err(ProgramError::Custom(u32::MAX - 1));
@@ -219,6 +224,91 @@ pub fn parse_pyth_price(pyth_price: &Price) -> Result<Decimal, ProgramError> {
     Ok(market_price)
 }
 
+/// describes the checks a price must pass before it is considered usable
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PriceValidationPolicy {
+    /// maximum number of slots since the aggregate price was published
+    pub max_slot_age: u64,
+    /// maximum ratio of confidence interval to price, expressed in basis points
+    pub max_confidence_bps: u64,
+    /// when true, the aggregate price status must be `PriceStatus::Trading`
+    pub require_trading: bool,
+    /// when true, positive exponents scale the price up instead of being rejected
+    pub allow_positive_exponent: bool,
+}
+
+impl Default for PriceValidationPolicy {
+    fn default() -> Self {
+        Self {
+            max_slot_age: STALE_AFTER_SLOTS_ELAPSED,
+            max_confidence_bps: 200,
+            require_trading: true,
+            allow_positive_exponent: false,
+        }
+    }
+}
+
+impl PriceValidationPolicy {
+    /// validates the given price against the policy as of `current_slot`,
+    /// returning the aggregate price as a Decimal
+    pub fn validate(&self, pyth_price: &Price, current_slot: u64) -> Result<Decimal, PythError> {
+        if pyth_price.magic != MAGIC
+            || pyth_price.ver != VERSION
+            || pyth_price.atype != AccountType::Price as u32
+        {
+            return Err(PythError::InvalidAccountHeader);
+        }
+        if pyth_price.ptype as u32 != PriceType::Price as u32 {
+            return Err(PythError::InvalidPriceType);
+        }
+        if self.require_trading && pyth_price.agg.status as u32 != PriceStatus::Trading as u32 {
+            return Err(PythError::PriceNotTrading);
+        }
+        if current_slot.saturating_sub(pyth_price.agg.pub_slot) > self.max_slot_age {
+            return Err(PythError::StalePrice);
+        }
+        if pyth_price.agg.price <= 0 {
+            return Err(PythError::NonPositivePrice);
+        }
+        let price = pyth_price.agg.price as u64;
+
+        // conf / price <= max_confidence_bps / 10_000
+        let scaled_conf = (pyth_price.agg.conf as u128)
+            .checked_mul(10_000)
+            .ok_or(PythError::MathOverflow)?;
+        let max_conf = (price as u128)
+            .checked_mul(self.max_confidence_bps as u128)
+            .ok_or(PythError::MathOverflow)?;
+        if scaled_conf > max_conf {
+            return Err(PythError::ConfidenceTooWide);
+        }
+
+        let exponent = pyth_price.expo.unsigned_abs();
+        let scale = 10u64.checked_pow(exponent).ok_or(PythError::MathOverflow)?;
+        let market_price = if pyth_price.expo > 0 {
+            if !self.allow_positive_exponent {
+                return Err(PythError::PositiveExponent);
+            }
+            Decimal::from(price).try_mul(scale)
+        } else {
+            Decimal::from(price).try_div(scale)
+        };
+        market_price.map_err(|_| PythError::MathOverflow)
+    }
+}
+
+/// loads the price account, and validates it against `policy` returning
+/// the aggregate price as a Decimal
+pub fn load_validated_pyth_price(
+    pyth_price_account_data: &[u8],
+    current_slot: u64,
+    policy: &PriceValidationPolicy,
+) -> Result<Decimal, PythError> {
+    let pyth_price =
+        load::<Price>(pyth_price_account_data).map_err(|_| PythError::InvalidAccountData)?;
+    policy.validate(pyth_price, current_slot)
+}
+
 #[cfg(test)]
 mod test {
 
@@ -235,4 +325,94 @@ mod test {
         let price = load_pyth_price(&tulip_price_account.data[..]).unwrap();
         println!("price {:#?}", price);
     }
+
+    fn mock_price(price: i64, conf: u64, expo: i32, pub_slot: u64) -> Price {
+        let mut pyth_price = Price {
+            magic: MAGIC,
+            ver: VERSION,
+            atype: AccountType::Price as u32,
+            expo,
+            ..Default::default()
+        };
+        pyth_price.agg.price = price;
+        pyth_price.agg.conf = conf;
+        pyth_price.agg.pub_slot = pub_slot;
+        pyth_price
+    }
+
+    #[test]
+    fn test_load_short_buffer() {
+        let pyth_price = mock_price(100, 1, -2, 10);
+        let data = bytemuck::bytes_of(&pyth_price);
+        assert!(load::<Price>(&data[0..data.len() - 1]).is_err());
+        assert!(load::<Price>(&[]).is_err());
+        assert_eq!(
+            load_validated_pyth_price(&data[0..16], 10, &PriceValidationPolicy::default()),
+            Err(PythError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn test_load_validated_pyth_price() {
+        let policy = PriceValidationPolicy::default();
+
+        let pyth_price = mock_price(150, 1, -2, 100);
+        let data = bytemuck::bytes_of(&pyth_price);
+        assert_eq!(
+            load_validated_pyth_price(data, 100 + STALE_AFTER_SLOTS_ELAPSED, &policy).unwrap(),
+            Decimal::from(3_u64).try_div(2_u64).unwrap()
+        );
+        assert_eq!(
+            load_validated_pyth_price(data, 101 + STALE_AFTER_SLOTS_ELAPSED, &policy),
+            Err(PythError::StalePrice)
+        );
+
+        // 2% of 150 is 3
+        let pyth_price = mock_price(150, 4, -2, 100);
+        assert_eq!(
+            policy.validate(&pyth_price, 100),
+            Err(PythError::ConfidenceTooWide)
+        );
+        let pyth_price = mock_price(150, 3, -2, 100);
+        assert!(policy.validate(&pyth_price, 100).is_ok());
+
+        let mut pyth_price = mock_price(150, 1, -2, 100);
+        pyth_price.agg.status = PriceStatus::Halted;
+        assert_eq!(
+            policy.validate(&pyth_price, 100),
+            Err(PythError::PriceNotTrading)
+        );
+        let relaxed = PriceValidationPolicy {
+            require_trading: false,
+            ..policy
+        };
+        assert!(relaxed.validate(&pyth_price, 100).is_ok());
+
+        let pyth_price = mock_price(0, 0, -2, 100);
+        assert_eq!(
+            policy.validate(&pyth_price, 100),
+            Err(PythError::NonPositivePrice)
+        );
+
+        let pyth_price = mock_price(15, 0, 2, 100);
+        assert_eq!(
+            policy.validate(&pyth_price, 100),
+            Err(PythError::PositiveExponent)
+        );
+        let relaxed = PriceValidationPolicy {
+            allow_positive_exponent: true,
+            ..policy
+        };
+        assert_eq!(
+            relaxed.validate(&pyth_price, 100).unwrap(),
+            Decimal::from(1500_u64)
+        );
+
+        let mut pyth_price = mock_price(150, 1, -2, 100);
+        pyth_price.magic = 0;
+        assert_eq!(
+            policy.validate(&pyth_price, 100),
+            Err(PythError::InvalidAccountHeader)
+        );
+    }
 }
@@ -0,0 +1,36 @@
+//! Error types
+
+use anchor_lang::solana_program::{decode_error::DecodeError, program_error::ProgramError};
+use num_derive::FromPrimitive;
+use thiserror::Error;
+
+/// Errors that may be returned when loading and validating switchboard aggregator accounts
+#[derive(Clone, Debug, Eq, Error, FromPrimitive, PartialEq)]
+pub enum SwitchboardError {
+    #[error("Aggregator account data is too short")]
+    InvalidAccountData,
+    #[error("Aggregator account has an invalid discriminator")]
+    InvalidDiscriminator,
+    #[error("Latest confirmed round has fewer results than required")]
+    InsufficientOracleResults,
+    #[error("Price has not been updated within the maximum slot age")]
+    StalePrice,
+    #[error("Price is zero or negative")]
+    NonPositivePrice,
+    #[error("Price standard deviation exceeds the maximum confidence ratio")]
+    ConfidenceTooWide,
+    #[error("Math operation overflow")]
+    MathOverflow,
+}
+
+impl From<SwitchboardError> for ProgramError {
+    fn from(e: SwitchboardError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
+
+impl<T> DecodeError<T> for SwitchboardError {
+    fn type_of() -> &'static str {
+        "Switchboard Error"
+    }
+}
@@ -0,0 +1,258 @@
+//! provides support for reading switchboard v2 aggregator accounts, which are used
+//! as a secondary price source by some lending protocols (ie: solend).
+//!
+//! only the fields of the aggregator required to price an asset are decoded, and the
+//! account is read at fixed offsets so that the switchboard crate is not required.
+
+pub mod error;
+
+use crate::math::{
+    common::{TryDiv, TryMul},
+    decimal::Decimal,
+};
+use crate::pyth::PriceValidationPolicy;
+use arrayref::array_ref;
+use error::SwitchboardError;
+use static_pubkey::static_pubkey;
+
+use anchor_lang::solana_program::pubkey::Pubkey;
+
+/// the switchboard v2 program id
+pub const SWITCHBOARD_V2_PROGRAM_ID: Pubkey =
+    static_pubkey!("SW1TCH7qEPTdLsDHRgPuMQjbQxKdH2aBStViMFnt64f");
+
+/// anchor account discriminator of `AggregatorAccountData`
+pub const AGGREGATOR_DISCRIMINATOR: [u8; 8] = [217, 230, 65, 101, 201, 162, 27, 125];
+
+/// offset of `min_oracle_results`, including the discriminator
+const MIN_ORACLE_RESULTS_OFFSET: usize = 236;
+/// offset of `latest_confirmed_round`, including the discriminator
+const LATEST_CONFIRMED_ROUND_OFFSET: usize = 341;
+/// size of the leading fields of an `AggregatorRound` that are decoded
+const ROUND_HEADER_LEN: usize = 9 + 8 + 8 + SWITCHBOARD_DECIMAL_LEN * 2;
+/// packed size of a `SwitchboardDecimal`
+const SWITCHBOARD_DECIMAL_LEN: usize = 20;
+
+/// minimum account size required to decode an aggregator
+pub const MIN_AGGREGATOR_LEN: usize = LATEST_CONFIRMED_ROUND_OFFSET + ROUND_HEADER_LEN;
+
+/// a decimal value represented as `mantissa * 10^-scale`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SwitchboardDecimal {
+    pub mantissa: i128,
+    pub scale: u32,
+}
+
+impl SwitchboardDecimal {
+    fn unpack(input: &[u8; SWITCHBOARD_DECIMAL_LEN]) -> Self {
+        let mantissa = array_ref![input, 0, 16];
+        let scale = array_ref![input, 16, 4];
+        Self {
+            mantissa: i128::from_le_bytes(*mantissa),
+            scale: u32::from_le_bytes(*scale),
+        }
+    }
+
+    /// converts the value into a Decimal, failing if it is negative
+    pub fn try_to_decimal(&self) -> Result<Decimal, SwitchboardError> {
+        let mantissa =
+            u128::try_from(self.mantissa).map_err(|_| SwitchboardError::NonPositivePrice)?;
+        let mut value = Decimal::from(mantissa);
+        let mut remaining = self.scale;
+        while remaining > 0 {
+            let step = remaining.min(18);
+            value = value
+                .try_div(10u64.pow(step))
+                .map_err(|_| SwitchboardError::MathOverflow)?;
+            remaining -= step;
+        }
+        Ok(value)
+    }
+}
+
+/// the decoded leading fields of an `AggregatorRound`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AggregatorRound {
+    pub num_success: u32,
+    pub num_error: u32,
+    pub is_closed: bool,
+    pub round_open_slot: u64,
+    pub round_open_timestamp: i64,
+    pub result: SwitchboardDecimal,
+    pub std_deviation: SwitchboardDecimal,
+}
+
+/// the decoded subset of a switchboard v2 `AggregatorAccountData`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Aggregator {
+    pub min_oracle_results: u32,
+    pub latest_confirmed_round: AggregatorRound,
+}
+
+impl Aggregator {
+    /// validates the latest confirmed round against `policy` as of `current_slot`,
+    /// returning the round result as a Decimal. the confidence ratio is checked
+    /// against the standard deviation of the round
+    pub fn validate(
+        &self,
+        current_slot: u64,
+        policy: &PriceValidationPolicy,
+    ) -> Result<Decimal, SwitchboardError> {
+        let round = &self.latest_confirmed_round;
+        if round.num_success < self.min_oracle_results.max(1) {
+            return Err(SwitchboardError::InsufficientOracleResults);
+        }
+        if current_slot.saturating_sub(round.round_open_slot) > policy.max_slot_age {
+            return Err(SwitchboardError::StalePrice);
+        }
+        if round.result.mantissa <= 0 {
+            return Err(SwitchboardError::NonPositivePrice);
+        }
+        let price = round.result.try_to_decimal()?;
+        let std_deviation = round.std_deviation.try_to_decimal().unwrap_or_default();
+        // std_deviation / price <= max_confidence_bps / 10_000
+        let max_std_deviation = price
+            .try_mul(policy.max_confidence_bps)
+            .and_then(|max| max.try_div(10_000_u64))
+            .map_err(|_| SwitchboardError::MathOverflow)?;
+        if std_deviation > max_std_deviation {
+            return Err(SwitchboardError::ConfidenceTooWide);
+        }
+        Ok(price)
+    }
+}
+
+/// decodes a switchboard v2 aggregator account
+pub fn load_aggregator(data: &[u8]) -> Result<Aggregator, SwitchboardError> {
+    if data.len() < MIN_AGGREGATOR_LEN {
+        return Err(SwitchboardError::InvalidAccountData);
+    }
+    if data[0..8] != AGGREGATOR_DISCRIMINATOR {
+        return Err(SwitchboardError::InvalidDiscriminator);
+    }
+    let min_oracle_results = array_ref![data, MIN_ORACLE_RESULTS_OFFSET, 4];
+    let round = array_ref![data, LATEST_CONFIRMED_ROUND_OFFSET, ROUND_HEADER_LEN];
+    let (
+        num_success,
+        num_error,
+        is_closed,
+        round_open_slot,
+        round_open_timestamp,
+        result,
+        std_deviation,
+    ) = arrayref::array_refs![
+        round,
+        4,
+        4,
+        1,
+        8,
+        8,
+        SWITCHBOARD_DECIMAL_LEN,
+        SWITCHBOARD_DECIMAL_LEN
+    ];
+    Ok(Aggregator {
+        min_oracle_results: u32::from_le_bytes(*min_oracle_results),
+        latest_confirmed_round: AggregatorRound {
+            num_success: u32::from_le_bytes(*num_success),
+            num_error: u32::from_le_bytes(*num_error),
+            is_closed: is_closed[0] != 0,
+            round_open_slot: u64::from_le_bytes(*round_open_slot),
+            round_open_timestamp: i64::from_le_bytes(*round_open_timestamp),
+            result: SwitchboardDecimal::unpack(result),
+            std_deviation: SwitchboardDecimal::unpack(std_deviation),
+        },
+    })
+}
+
+/// loads the aggregator account, and validates it against `policy` returning
+/// the latest confirmed round result as a Decimal
+pub fn load_validated_switchboard_price(
+    aggregator_account_data: &[u8],
+    current_slot: u64,
+    policy: &PriceValidationPolicy,
+) -> Result<Decimal, SwitchboardError> {
+    load_aggregator(aggregator_account_data)?.validate(current_slot, policy)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn mock_aggregator_data(
+        mantissa: i128,
+        scale: u32,
+        std_deviation: i128,
+        round_open_slot: u64,
+    ) -> Vec<u8> {
+        let mut data = vec![0_u8; MIN_AGGREGATOR_LEN];
+        data[0..8].copy_from_slice(&AGGREGATOR_DISCRIMINATOR);
+        data[MIN_ORACLE_RESULTS_OFFSET..MIN_ORACLE_RESULTS_OFFSET + 4]
+            .copy_from_slice(&1_u32.to_le_bytes());
+        let round = &mut data[LATEST_CONFIRMED_ROUND_OFFSET..];
+        round[0..4].copy_from_slice(&3_u32.to_le_bytes());
+        round[8] = 1;
+        round[9..17].copy_from_slice(&round_open_slot.to_le_bytes());
+        round[25..41].copy_from_slice(&mantissa.to_le_bytes());
+        round[41..45].copy_from_slice(&scale.to_le_bytes());
+        round[45..61].copy_from_slice(&std_deviation.to_le_bytes());
+        round[61..65].copy_from_slice(&scale.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_load_aggregator() {
+        let data = mock_aggregator_data(1_234_500, 4, 1, 50);
+        let aggregator = load_aggregator(&data).unwrap();
+        assert_eq!(aggregator.min_oracle_results, 1);
+        assert_eq!(aggregator.latest_confirmed_round.num_success, 3);
+        assert!(aggregator.latest_confirmed_round.is_closed);
+        assert_eq!(aggregator.latest_confirmed_round.round_open_slot, 50);
+        assert_eq!(
+            aggregator.latest_confirmed_round.result,
+            SwitchboardDecimal {
+                mantissa: 1_234_500,
+                scale: 4
+            }
+        );
+        assert_eq!(
+            load_aggregator(&data[0..data.len() - 1]),
+            Err(SwitchboardError::InvalidAccountData)
+        );
+        let mut bad = data.clone();
+        bad[0] = 0;
+        assert_eq!(
+            load_aggregator(&bad),
+            Err(SwitchboardError::InvalidDiscriminator)
+        );
+    }
+
+    #[test]
+    fn test_load_validated_switchboard_price() {
+        let policy = PriceValidationPolicy::default();
+        let data = mock_aggregator_data(1_234_500, 4, 1, 50);
+        assert_eq!(
+            load_validated_switchboard_price(&data, 60, &policy).unwrap(),
+            Decimal::from(1_234_500_u64).try_div(10_000_u64).unwrap()
+        );
+        assert_eq!(
+            load_validated_switchboard_price(&data, 51 + policy.max_slot_age, &policy),
+            Err(SwitchboardError::StalePrice)
+        );
+        let data = mock_aggregator_data(1_000, 0, 21, 50);
+        assert_eq!(
+            load_validated_switchboard_price(&data, 60, &policy),
+            Err(SwitchboardError::ConfidenceTooWide)
+        );
+        let data = mock_aggregator_data(-1, 0, 0, 50);
+        assert_eq!(
+            load_validated_switchboard_price(&data, 60, &policy),
+            Err(SwitchboardError::NonPositivePrice)
+        );
+        // scales beyond what fits in a single u64 division
+        let value = SwitchboardDecimal {
+            mantissa: 5 * 10_i128.pow(30),
+            scale: 30,
+        };
+        assert_eq!(value.try_to_decimal().unwrap(), Decimal::from(5_u64));
+    }
+}
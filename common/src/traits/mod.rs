@@ -1,8 +1,10 @@
-//! provides a tokenized vault shares trait and pausable action trait similar to OpenZeppelin's pausable library
+//! provides a tokenized vault shares trait, a pausable action trait similar to OpenZeppelin's pausable library,
+//! and an oracle trait implemented for the supported price feeds
 
 #![allow(clippy::needless_lifetimes)]
 #![allow(clippy::bool_assert_comparison)]
 #![allow(clippy::too_many_arguments)]
 
+pub mod oracle;
 pub mod pausable;
 pub mod vault;
@@ -0,0 +1,205 @@
+//! Oracle provides a common interface over the price feeds supported by the sdk,
+//! allowing reserve refresh and obligation health calculations to be performed
+//! against whichever feed a reserve is configured with.
+
+use crate::math::decimal::Decimal;
+use crate::pyth::{self, error::PythError, Price, PriceValidationPolicy};
+use crate::switchboard::{self, error::SwitchboardError, Aggregator};
+use thiserror::Error;
+
+/// Errors that may be returned when reading a price from an oracle
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub enum OracleError {
+    #[error("pyth: {0}")]
+    Pyth(#[from] PythError),
+    #[error("switchboard: {0}")]
+    Switchboard(#[from] SwitchboardError),
+    #[error("Account is neither a pyth price nor a switchboard aggregator")]
+    UnknownOracleAccount,
+    #[error("Oracle has no price set")]
+    MissingPrice,
+    #[error("Price has not been updated within the maximum slot age")]
+    StalePrice,
+}
+
+/// defines traits required for a type to be used as a price feed
+pub trait Oracle {
+    /// returns the price after validating it against `policy` as of `current_slot`
+    fn validated_price(
+        &self,
+        current_slot: u64,
+        policy: &PriceValidationPolicy,
+    ) -> Result<Decimal, OracleError>;
+    /// returns the slot at which the price was last published
+    fn last_update_slot(&self) -> u64;
+}
+
+impl Oracle for Price {
+    fn validated_price(
+        &self,
+        current_slot: u64,
+        policy: &PriceValidationPolicy,
+    ) -> Result<Decimal, OracleError> {
+        Ok(policy.validate(self, current_slot)?)
+    }
+    fn last_update_slot(&self) -> u64 {
+        self.agg.pub_slot
+    }
+}
+
+impl Oracle for Aggregator {
+    fn validated_price(
+        &self,
+        current_slot: u64,
+        policy: &PriceValidationPolicy,
+    ) -> Result<Decimal, OracleError> {
+        Ok(self.validate(current_slot, policy)?)
+    }
+    fn last_update_slot(&self) -> u64 {
+        self.latest_confirmed_round.round_open_slot
+    }
+}
+
+/// an oracle account decoded from raw account data
+#[derive(Clone)]
+pub enum OracleAccount {
+    Pyth(Box<Price>),
+    Switchboard(Aggregator),
+}
+
+impl OracleAccount {
+    /// decodes the account data as either a pyth price account, or a switchboard
+    /// aggregator account, based on the pyth magic number or anchor discriminator
+    pub fn from_account_data(data: &[u8]) -> Result<Self, OracleError> {
+        if data.len() >= 8 && data[0..8] == switchboard::AGGREGATOR_DISCRIMINATOR {
+            return Ok(Self::Switchboard(switchboard::load_aggregator(data)?));
+        }
+        match pyth::load::<Price>(data) {
+            Ok(price) if price.magic == pyth::MAGIC => Ok(Self::Pyth(Box::new(*price))),
+            _ => Err(OracleError::UnknownOracleAccount),
+        }
+    }
+}
+
+impl Oracle for OracleAccount {
+    fn validated_price(
+        &self,
+        current_slot: u64,
+        policy: &PriceValidationPolicy,
+    ) -> Result<Decimal, OracleError> {
+        match self {
+            Self::Pyth(price) => price.validated_price(current_slot, policy),
+            Self::Switchboard(aggregator) => aggregator.validated_price(current_slot, policy),
+        }
+    }
+    fn last_update_slot(&self) -> u64 {
+        match self {
+            Self::Pyth(price) => price.last_update_slot(),
+            Self::Switchboard(aggregator) => aggregator.last_update_slot(),
+        }
+    }
+}
+
+/// an in-memory oracle, intended for use in tests and simulations
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MockOracle {
+    pub price: Option<Decimal>,
+    pub slot: u64,
+}
+
+impl MockOracle {
+    pub fn new(price: Decimal, slot: u64) -> Self {
+        Self {
+            price: Some(price),
+            slot,
+        }
+    }
+    /// updates the price, and the slot at which it was published
+    pub fn set_price(&mut self, price: Decimal, slot: u64) {
+        self.price = Some(price);
+        self.slot = slot;
+    }
+}
+
+impl Oracle for MockOracle {
+    /// only the slot age of the policy is checked
+    fn validated_price(
+        &self,
+        current_slot: u64,
+        policy: &PriceValidationPolicy,
+    ) -> Result<Decimal, OracleError> {
+        let price = self.price.ok_or(OracleError::MissingPrice)?;
+        if current_slot.saturating_sub(self.slot) > policy.max_slot_age {
+            return Err(OracleError::StalePrice);
+        }
+        Ok(price)
+    }
+    fn last_update_slot(&self) -> u64 {
+        self.slot
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pyth::{AccountType, MAGIC, VERSION};
+
+    #[test]
+    fn test_mock_oracle() {
+        let policy = PriceValidationPolicy::default();
+        let mut oracle = MockOracle::default();
+        assert_eq!(
+            oracle.validated_price(0, &policy),
+            Err(OracleError::MissingPrice)
+        );
+        oracle.set_price(Decimal::from(2_u64), 10);
+        assert_eq!(
+            oracle.validated_price(10, &policy).unwrap(),
+            Decimal::from(2_u64)
+        );
+        assert_eq!(
+            oracle.validated_price(11 + policy.max_slot_age, &policy),
+            Err(OracleError::StalePrice)
+        );
+        assert_eq!(oracle.last_update_slot(), 10);
+    }
+
+    #[test]
+    fn test_oracle_account_from_account_data() {
+        let mut price = Price {
+            magic: MAGIC,
+            ver: VERSION,
+            atype: AccountType::Price as u32,
+            expo: -1,
+            ..Default::default()
+        };
+        price.agg.price = 25;
+        price.agg.pub_slot = 7;
+        let account = OracleAccount::from_account_data(bytemuck::bytes_of(&price)).unwrap();
+        assert!(matches!(account, OracleAccount::Pyth(_)));
+        assert_eq!(account.last_update_slot(), 7);
+        assert_eq!(
+            account
+                .validated_price(7, &PriceValidationPolicy::default())
+                .unwrap()
+                .to_string(),
+            "2.500000000000000000"
+        );
+
+        let mut data = vec![0_u8; switchboard::MIN_AGGREGATOR_LEN];
+        data[0..8].copy_from_slice(&switchboard::AGGREGATOR_DISCRIMINATOR);
+        let account = OracleAccount::from_account_data(&data).unwrap();
+        assert!(matches!(account, OracleAccount::Switchboard(_)));
+        assert_eq!(
+            account.validated_price(0, &PriceValidationPolicy::default()),
+            Err(OracleError::Switchboard(
+                SwitchboardError::InsufficientOracleResults
+            ))
+        );
+
+        assert_eq!(
+            OracleAccount::from_account_data(&[0_u8; 16]).err(),
+            Some(OracleError::UnknownOracleAccount)
+        );
+    }
+}
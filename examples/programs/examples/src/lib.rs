@@ -1173,7 +1173,10 @@ pub mod examples {
                 ],
             )?
         }
-        let farm = tulipv2_sdk_levfarm::accounts::Farms::from(farm);
+        let farm = tulipv2_sdk_levfarm::accounts::Farms::try_from(farm).map_err(|err| {
+            msg!("{}", err);
+            ProgramError::InvalidArgument
+        })?;
         let ix = tulipv2_sdk_levfarm::helpers::new_create_user_farm_ix(
             ctx.accounts.authority.key(),
             farm,
@@ -1203,7 +1206,10 @@ pub mod examples {
         farm: u64,
         obligation_index: u64,
     ) -> Result<()> {
-        let farm = tulipv2_sdk_levfarm::accounts::Farms::from(farm);
+        let farm = tulipv2_sdk_levfarm::accounts::Farms::try_from(farm).map_err(|err| {
+            msg!("{}", err);
+            ProgramError::InvalidArgument
+        })?;
         let ix = tulipv2_sdk_levfarm::helpers::new_create_user_farm_obligation_ix(
             ctx.accounts.authority.key(),
             ctx.accounts.user_farm.key(),
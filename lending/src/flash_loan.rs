@@ -0,0 +1,231 @@
+//! helpers for taking flash loans from lending reserves.
+//!
+//! the lending program does not expose separate borrow and repay instructions for flash loans.
+//! instead the `FlashLoan` instruction transfers the loan to the destination account, invokes
+//! the receiver program with every account that follows it, and then checks that the loan plus
+//! fees has been returned to the reserve liquidity supply. wrapping a set of inner instructions
+//! with a flash loan therefore means passing their accounts to a receiver program which executes
+//! them, and repays `FlashLoanAmounts::repay_amount` before returning.
+
+use crate::instruction::flash_loan;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use tulipv2_sdk_common::lending::{error::LendingError, reserve::Reserve};
+
+/// Amounts borrowed and owed by a flash loan
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FlashLoanAmounts {
+    /// amount of liquidity borrowed
+    pub amount: u64,
+    /// portion of the fee paid to the reserve fee receiver
+    pub protocol_fee: u64,
+    /// portion of the fee paid to the host fee receiver
+    pub host_fee: u64,
+    /// amount that must be returned to the reserve liquidity supply, inclusive of all fees
+    pub repay_amount: u64,
+}
+
+impl FlashLoanAmounts {
+    /// total fee charged for the flash loan
+    pub fn total_fee(&self) -> u64 {
+        self.protocol_fee + self.host_fee
+    }
+}
+
+/// Accounts used to take a flash loan from a reserve
+#[derive(Clone, Copy, Debug)]
+pub struct FlashLoanAccounts {
+    /// address of the reserve to borrow from
+    pub reserve: Pubkey,
+    /// the lending market of the reserve
+    pub lending_market: Pubkey,
+    /// token account receiving the loan, minted by the reserve liquidity mint
+    pub destination_liquidity: Pubkey,
+    /// token account receiving the host portion of the fee
+    pub host_fee_receiver: Pubkey,
+    /// program invoked by the lending program once the loan has been transferred
+    pub receiver_program_id: Pubkey,
+}
+
+/// calculates the fees charged for borrowing `amount` from the reserve, and the
+/// exact amount which must be returned to the reserve liquidity supply
+pub fn calculate_flash_loan_amounts(
+    reserve: &Reserve,
+    amount: u64,
+) -> Result<FlashLoanAmounts, ProgramError> {
+    let (total_fee, host_fee) = reserve
+        .config
+        .fees
+        .calculate_flash_loan_fees(amount.into())?;
+    let repay_amount = amount
+        .checked_add(total_fee)
+        .ok_or(LendingError::MathOverflow)?;
+    Ok(FlashLoanAmounts {
+        amount,
+        protocol_fee: total_fee - host_fee,
+        host_fee,
+        repay_amount,
+    })
+}
+
+/// builds a `FlashLoan` instruction whose receiver program is passed every account,
+/// including the program id, referenced by `inner_instructions`.
+///
+/// accounts are de-duplicated in the order they are first referenced, and are marked
+/// writable or signer if any inner instruction requires it. the receiver program is
+/// responsible for executing the inner logic and returning `repay_amount` to the reserve
+/// liquidity supply, which is `source_liquidity` of the `FlashLoan` instruction
+pub fn flash_loan_with_inner_instructions(
+    program_id: Pubkey,
+    reserve: &Reserve,
+    amount: u64,
+    accounts: &FlashLoanAccounts,
+    inner_instructions: &[Instruction],
+) -> Result<(FlashLoanAmounts, Instruction), ProgramError> {
+    let amounts = calculate_flash_loan_amounts(reserve, amount)?;
+    let instruction = flash_loan(
+        program_id,
+        amount,
+        reserve.liquidity.supply_pubkey,
+        accounts.destination_liquidity,
+        accounts.reserve,
+        reserve.liquidity.fee_receiver,
+        accounts.host_fee_receiver,
+        accounts.lending_market,
+        accounts.receiver_program_id,
+        receiver_accounts(inner_instructions),
+    );
+    Ok((amounts, instruction))
+}
+
+/// merges the accounts of the instructions, preserving the order they are first referenced in
+pub fn receiver_accounts(instructions: &[Instruction]) -> Vec<AccountMeta> {
+    let mut accounts: Vec<AccountMeta> = Vec::new();
+    let metas = instructions.iter().flat_map(|ix| {
+        std::iter::once(AccountMeta::new_readonly(ix.program_id, false))
+            .chain(ix.accounts.iter().cloned())
+    });
+    for meta in metas {
+        match accounts
+            .iter_mut()
+            .find(|account| account.pubkey == meta.pubkey)
+        {
+            Some(account) => {
+                account.is_writable |= meta.is_writable;
+                account.is_signer |= meta.is_signer;
+            }
+            None => accounts.push(meta),
+        }
+    }
+    accounts
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tulipv2_sdk_common::lending::reserve::{ReserveConfig, ReserveFees, ReserveLiquidity};
+
+    fn mock_reserve(flash_loan_fee_wad: u64, host_fee_percentage: u8) -> Reserve {
+        Reserve {
+            liquidity: ReserveLiquidity {
+                supply_pubkey: Pubkey::new_unique(),
+                fee_receiver: Pubkey::new_unique(),
+                ..Default::default()
+            },
+            config: ReserveConfig {
+                fees: ReserveFees {
+                    flash_loan_fee_wad,
+                    host_fee_percentage,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_calculate_flash_loan_amounts() {
+        // 0.3% fee, 20% of which goes to the host
+        let reserve = mock_reserve(3_000_000_000_000_000, 20);
+        let amounts = calculate_flash_loan_amounts(&reserve, 1_000_000).unwrap();
+        assert_eq!(
+            amounts,
+            FlashLoanAmounts {
+                amount: 1_000_000,
+                protocol_fee: 2_400,
+                host_fee: 600,
+                repay_amount: 1_003_000,
+            }
+        );
+        assert_eq!(amounts.total_fee(), 3_000);
+
+        // minimum fee of 1 token to the owner and 1 to the host
+        let amounts = calculate_flash_loan_amounts(&reserve, 100).unwrap();
+        assert_eq!(amounts.repay_amount, 102);
+
+        // no fees configured
+        let reserve = mock_reserve(0, 0);
+        let amounts = calculate_flash_loan_amounts(&reserve, 100).unwrap();
+        assert_eq!(amounts.repay_amount, 100);
+        assert!(calculate_flash_loan_amounts(&mock_reserve(3_000_000_000_000_000, 20), 1).is_err());
+    }
+
+    #[test]
+    fn test_flash_loan_with_inner_instructions() {
+        let reserve = mock_reserve(3_000_000_000_000_000, 20);
+        let accounts = FlashLoanAccounts {
+            reserve: Pubkey::new_unique(),
+            lending_market: Pubkey::new_unique(),
+            destination_liquidity: Pubkey::new_unique(),
+            host_fee_receiver: Pubkey::new_unique(),
+            receiver_program_id: Pubkey::new_unique(),
+        };
+        let (program_a, program_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (shared, signer) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let inner_instructions = vec![
+            Instruction {
+                program_id: program_a,
+                accounts: vec![
+                    AccountMeta::new_readonly(shared, false),
+                    AccountMeta::new_readonly(signer, true),
+                ],
+                data: vec![],
+            },
+            Instruction {
+                program_id: program_b,
+                accounts: vec![
+                    AccountMeta::new(shared, false),
+                    AccountMeta::new_readonly(program_a, false),
+                ],
+                data: vec![],
+            },
+        ];
+        let program_id = Pubkey::new_unique();
+        let (amounts, ix) = flash_loan_with_inner_instructions(
+            program_id,
+            &reserve,
+            1_000_000,
+            &accounts,
+            &inner_instructions,
+        )
+        .unwrap();
+        assert_eq!(amounts.repay_amount, 1_003_000);
+        assert_eq!(ix.program_id, program_id);
+        assert_eq!(ix.accounts[0].pubkey, reserve.liquidity.supply_pubkey);
+        assert_eq!(ix.accounts[3].pubkey, reserve.liquidity.fee_receiver);
+        assert_eq!(ix.accounts[8].pubkey, accounts.receiver_program_id);
+        assert_eq!(
+            ix.accounts[9..].to_vec(),
+            vec![
+                AccountMeta::new_readonly(program_a, false),
+                AccountMeta::new(shared, false),
+                AccountMeta::new_readonly(signer, true),
+                AccountMeta::new_readonly(program_b, false),
+            ]
+        );
+    }
+}
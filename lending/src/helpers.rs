@@ -1,6 +1,8 @@
 //! helper functions used to cpi to the lending program
 
-use solana_program::{account_info::AccountInfo, program_error::ProgramError};
+use solana_program::{
+    account_info::AccountInfo, instruction::AccountMeta, program_error::ProgramError,
+};
 
 /// creates, and invokes a reserve refresh instruction
 pub fn refresh_reserve<'info>(
@@ -127,3 +129,347 @@ pub fn redeem_reserve_collateral<'info>(
     )?;
     Ok(())
 }
+
+/// creates and invokes an init obligation instruction
+#[allow(clippy::too_many_arguments)]
+pub fn init_obligation<'info>(
+    lending_program_id: &AccountInfo<'info>,
+    obligation: &AccountInfo<'info>,
+    lending_market: &AccountInfo<'info>,
+    obligation_owner: &AccountInfo<'info>,
+    clock: &AccountInfo<'info>,
+    rent: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<(), ProgramError> {
+    let ix = super::instruction::init_obligation(
+        *lending_program_id.key,
+        *obligation.key,
+        *lending_market.key,
+        *obligation_owner.key,
+    );
+    solana_program::program::invoke_signed(
+        &ix,
+        &[
+            obligation.clone(),
+            lending_market.clone(),
+            obligation_owner.clone(),
+            clock.clone(),
+            rent.clone(),
+            token_program.clone(),
+        ],
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+/// creates and invokes a refresh obligation instruction. `reserves` must contain
+/// all deposit reserves in order, followed by all borrow reserves in order
+pub fn refresh_obligation<'info>(
+    lending_program_id: &AccountInfo<'info>,
+    obligation: &AccountInfo<'info>,
+    clock: &AccountInfo<'info>,
+    reserves: &[AccountInfo<'info>],
+    signer_seeds: &[&[&[u8]]],
+) -> Result<(), ProgramError> {
+    let ix = super::instruction::refresh_obligation(
+        *lending_program_id.key,
+        *obligation.key,
+        reserves.iter().map(|reserve| *reserve.key).collect(),
+    );
+    let mut account_infos = Vec::with_capacity(reserves.len() + 2);
+    account_infos.push(obligation.clone());
+    account_infos.push(clock.clone());
+    account_infos.extend_from_slice(reserves);
+    solana_program::program::invoke_signed(&ix, &account_infos, signer_seeds)?;
+    Ok(())
+}
+
+/// creates and invokes a deposit obligation collateral instruction
+#[allow(clippy::too_many_arguments)]
+pub fn deposit_obligation_collateral<'info>(
+    lending_program_id: &AccountInfo<'info>,
+    source_collateral: &AccountInfo<'info>,
+    destination_collateral: &AccountInfo<'info>,
+    deposit_reserve: &AccountInfo<'info>,
+    obligation: &AccountInfo<'info>,
+    lending_market: &AccountInfo<'info>,
+    obligation_owner: &AccountInfo<'info>,
+    user_transfer_authority: &AccountInfo<'info>,
+    clock: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> Result<(), ProgramError> {
+    let ix = super::instruction::deposit_obligation_collateral(
+        *lending_program_id.key,
+        amount,
+        *source_collateral.key,
+        *destination_collateral.key,
+        *deposit_reserve.key,
+        *obligation.key,
+        *lending_market.key,
+        *obligation_owner.key,
+        *user_transfer_authority.key,
+    );
+    solana_program::program::invoke_signed(
+        &ix,
+        &[
+            source_collateral.clone(),
+            destination_collateral.clone(),
+            deposit_reserve.clone(),
+            obligation.clone(),
+            lending_market.clone(),
+            obligation_owner.clone(),
+            user_transfer_authority.clone(),
+            clock.clone(),
+            token_program.clone(),
+        ],
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+/// creates and invokes a withdraw obligation collateral instruction
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw_obligation_collateral<'info>(
+    lending_program_id: &AccountInfo<'info>,
+    source_collateral: &AccountInfo<'info>,
+    destination_collateral: &AccountInfo<'info>,
+    withdraw_reserve: &AccountInfo<'info>,
+    obligation: &AccountInfo<'info>,
+    lending_market: &AccountInfo<'info>,
+    lending_market_authority: &AccountInfo<'info>,
+    obligation_owner: &AccountInfo<'info>,
+    clock: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> Result<(), ProgramError> {
+    let ix = super::instruction::withdraw_obligation_collateral(
+        *lending_program_id.key,
+        amount,
+        *source_collateral.key,
+        *destination_collateral.key,
+        *withdraw_reserve.key,
+        *obligation.key,
+        *lending_market.key,
+        *obligation_owner.key,
+    );
+    solana_program::program::invoke_signed(
+        &ix,
+        &[
+            source_collateral.clone(),
+            destination_collateral.clone(),
+            withdraw_reserve.clone(),
+            obligation.clone(),
+            lending_market.clone(),
+            lending_market_authority.clone(),
+            obligation_owner.clone(),
+            clock.clone(),
+            token_program.clone(),
+        ],
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+/// creates and invokes a borrow obligation liquidity instruction
+#[allow(clippy::too_many_arguments)]
+pub fn borrow_obligation_liquidity<'info>(
+    lending_program_id: &AccountInfo<'info>,
+    source_liquidity: &AccountInfo<'info>,
+    destination_liquidity: &AccountInfo<'info>,
+    borrow_reserve: &AccountInfo<'info>,
+    borrow_reserve_liquidity_fee_receiver: &AccountInfo<'info>,
+    obligation: &AccountInfo<'info>,
+    lending_market: &AccountInfo<'info>,
+    lending_market_authority: &AccountInfo<'info>,
+    obligation_owner: &AccountInfo<'info>,
+    clock: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    borrow_authorizer: &AccountInfo<'info>,
+    host_fee_receiver: Option<&AccountInfo<'info>>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> Result<(), ProgramError> {
+    let ix = super::instruction::borrow_obligation_liquidity(
+        *lending_program_id.key,
+        amount,
+        *source_liquidity.key,
+        *destination_liquidity.key,
+        *borrow_reserve.key,
+        *borrow_reserve_liquidity_fee_receiver.key,
+        *obligation.key,
+        *lending_market.key,
+        *obligation_owner.key,
+        *borrow_authorizer.key,
+        host_fee_receiver.map(|host_fee_receiver| *host_fee_receiver.key),
+    );
+    let mut account_infos = vec![
+        source_liquidity.clone(),
+        destination_liquidity.clone(),
+        borrow_reserve.clone(),
+        borrow_reserve_liquidity_fee_receiver.clone(),
+        obligation.clone(),
+        lending_market.clone(),
+        lending_market_authority.clone(),
+        obligation_owner.clone(),
+        clock.clone(),
+        token_program.clone(),
+        borrow_authorizer.clone(),
+    ];
+    if let Some(host_fee_receiver) = host_fee_receiver {
+        account_infos.push(host_fee_receiver.clone());
+    }
+    solana_program::program::invoke_signed(&ix, &account_infos, signer_seeds)?;
+    Ok(())
+}
+
+/// creates and invokes a repay obligation liquidity instruction
+#[allow(clippy::too_many_arguments)]
+pub fn repay_obligation_liquidity<'info>(
+    lending_program_id: &AccountInfo<'info>,
+    source_liquidity: &AccountInfo<'info>,
+    destination_liquidity: &AccountInfo<'info>,
+    repay_reserve: &AccountInfo<'info>,
+    obligation: &AccountInfo<'info>,
+    lending_market: &AccountInfo<'info>,
+    user_transfer_authority: &AccountInfo<'info>,
+    clock: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> Result<(), ProgramError> {
+    let ix = super::instruction::repay_obligation_liquidity(
+        *lending_program_id.key,
+        amount,
+        *source_liquidity.key,
+        *destination_liquidity.key,
+        *repay_reserve.key,
+        *obligation.key,
+        *lending_market.key,
+        *user_transfer_authority.key,
+    );
+    solana_program::program::invoke_signed(
+        &ix,
+        &[
+            source_liquidity.clone(),
+            destination_liquidity.clone(),
+            repay_reserve.clone(),
+            obligation.clone(),
+            lending_market.clone(),
+            user_transfer_authority.clone(),
+            clock.clone(),
+            token_program.clone(),
+        ],
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+/// creates and invokes a liquidate obligation instruction
+#[allow(clippy::too_many_arguments)]
+pub fn liquidate_obligation<'info>(
+    lending_program_id: &AccountInfo<'info>,
+    source_liquidity: &AccountInfo<'info>,
+    destination_collateral: &AccountInfo<'info>,
+    repay_reserve: &AccountInfo<'info>,
+    repay_reserve_liquidity_supply: &AccountInfo<'info>,
+    withdraw_reserve: &AccountInfo<'info>,
+    withdraw_reserve_collateral_supply: &AccountInfo<'info>,
+    obligation: &AccountInfo<'info>,
+    lending_market: &AccountInfo<'info>,
+    lending_market_authority: &AccountInfo<'info>,
+    user_transfer_authority: &AccountInfo<'info>,
+    clock: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> Result<(), ProgramError> {
+    let ix = super::instruction::liquidate_obligation(
+        *lending_program_id.key,
+        amount,
+        *source_liquidity.key,
+        *destination_collateral.key,
+        *repay_reserve.key,
+        *repay_reserve_liquidity_supply.key,
+        *withdraw_reserve.key,
+        *withdraw_reserve_collateral_supply.key,
+        *obligation.key,
+        *lending_market.key,
+        *user_transfer_authority.key,
+    );
+    solana_program::program::invoke_signed(
+        &ix,
+        &[
+            source_liquidity.clone(),
+            destination_collateral.clone(),
+            repay_reserve.clone(),
+            repay_reserve_liquidity_supply.clone(),
+            withdraw_reserve.clone(),
+            withdraw_reserve_collateral_supply.clone(),
+            obligation.clone(),
+            lending_market.clone(),
+            lending_market_authority.clone(),
+            user_transfer_authority.clone(),
+            clock.clone(),
+            token_program.clone(),
+        ],
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+/// creates and invokes a flash loan instruction. `flash_loan_receiver_accounts` are
+/// forwarded to the receiving program's `ReceiveFlashLoan` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn flash_loan<'info>(
+    lending_program_id: &AccountInfo<'info>,
+    source_liquidity: &AccountInfo<'info>,
+    destination_liquidity: &AccountInfo<'info>,
+    reserve: &AccountInfo<'info>,
+    reserve_liquidity_fee_receiver: &AccountInfo<'info>,
+    host_fee_receiver: &AccountInfo<'info>,
+    lending_market: &AccountInfo<'info>,
+    lending_market_authority: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    flash_loan_receiver_program: &AccountInfo<'info>,
+    flash_loan_receiver_accounts: &[AccountInfo<'info>],
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> Result<(), ProgramError> {
+    let ix = super::instruction::flash_loan(
+        *lending_program_id.key,
+        amount,
+        *source_liquidity.key,
+        *destination_liquidity.key,
+        *reserve.key,
+        *reserve_liquidity_fee_receiver.key,
+        *host_fee_receiver.key,
+        *lending_market.key,
+        *flash_loan_receiver_program.key,
+        flash_loan_receiver_accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: *account.key,
+                is_signer: account.is_signer,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+    );
+    let mut account_infos = vec![
+        source_liquidity.clone(),
+        destination_liquidity.clone(),
+        reserve.clone(),
+        reserve_liquidity_fee_receiver.clone(),
+        host_fee_receiver.clone(),
+        lending_market.clone(),
+        lending_market_authority.clone(),
+        token_program.clone(),
+        flash_loan_receiver_program.clone(),
+    ];
+    account_infos.extend_from_slice(flash_loan_receiver_accounts);
+    solana_program::program::invoke_signed(&ix, &account_infos, signer_seeds)?;
+    Ok(())
+}
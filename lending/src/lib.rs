@@ -1,5 +1,7 @@
 //! instructions and helper functions for lending assets to the Tulip lending program
 //! for usage with leveraged yield farming
 
+pub mod flash_loan;
 pub mod helpers;
 pub mod instruction;
+pub mod liquidator;
@@ -0,0 +1,339 @@
+//! helpers for liquidation keepers, which scan lending obligations for ones that are
+//! unhealthy, rank them by the liquidation bonus they would pay out, and build the
+//! instructions needed to liquidate them
+
+use crate::instruction::{liquidate_obligation, refresh_obligation, refresh_reserve};
+use solana_program::{instruction::Instruction, msg, program_error::ProgramError, pubkey::Pubkey};
+use std::{cmp::Reverse, collections::HashMap};
+use tulipv2_sdk_common::lending::{
+    error::LendingError,
+    lending_obligation::{pseudo_refresh_lending_obligation, LendingObligation},
+    reserve::{CalculateLiquidationResult, Reserve},
+};
+use tulipv2_sdk_common::math::{
+    common::{TryDiv, TryMul, TrySub},
+    decimal::Decimal,
+};
+
+/// An unhealthy obligation, along with the most profitable repay and withdraw reserve pair
+#[derive(Debug)]
+pub struct LiquidationOpportunity {
+    /// address of the obligation to liquidate
+    pub obligation: Pubkey,
+    /// reserve of the borrow that is repaid
+    pub repay_reserve: Pubkey,
+    /// reserve of the collateral that is withdrawn
+    pub withdraw_reserve: Pubkey,
+    /// amounts repaid, settled and withdrawn by the liquidation
+    pub liquidation: CalculateLiquidationResult,
+    /// market value of the liquidity repaid
+    pub repay_value: Decimal,
+    /// market value of the liquidity underlying the collateral withdrawn
+    pub withdraw_value: Decimal,
+    /// value received in excess of the value repaid
+    pub bonus_value: Decimal,
+}
+
+/// Token accounts and signer used by a liquidator
+#[derive(Clone, Copy, Debug)]
+pub struct LiquidatorAccounts {
+    /// token account holding the liquidity to repay, must be of the repay reserve mint
+    pub source_liquidity: Pubkey,
+    /// token account receiving the collateral, must be of the withdraw reserve collateral mint
+    pub destination_collateral: Pubkey,
+    /// owner or delegate of `source_liquidity`
+    pub user_transfer_authority: Pubkey,
+}
+
+impl LiquidationOpportunity {
+    /// returns the instructions which refresh every reserve used by the obligation,
+    /// refresh the obligation, and then liquidate it
+    pub fn instructions(
+        &self,
+        program_id: Pubkey,
+        obligation: &LendingObligation,
+        reserves: &HashMap<Pubkey, Reserve>,
+        accounts: &LiquidatorAccounts,
+    ) -> Result<Vec<Instruction>, ProgramError> {
+        let repay_reserve = find_reserve(reserves, &self.repay_reserve)?;
+        let withdraw_reserve = find_reserve(reserves, &self.withdraw_reserve)?;
+
+        let reserve_keys = obligation
+            .deposits
+            .iter()
+            .map(|collateral| collateral.deposit_reserve)
+            .chain(
+                obligation
+                    .borrows
+                    .iter()
+                    .map(|liquidity| liquidity.borrow_reserve),
+            )
+            .collect::<Vec<_>>();
+        let mut instructions = Vec::with_capacity(reserve_keys.len() + 2);
+        let mut refreshed = Vec::with_capacity(reserve_keys.len());
+        for reserve_key in reserve_keys.iter() {
+            if refreshed.contains(reserve_key) {
+                continue;
+            }
+            let reserve = find_reserve(reserves, reserve_key)?;
+            instructions.push(refresh_reserve(
+                program_id,
+                *reserve_key,
+                reserve.liquidity.oracle_pubkey,
+            ));
+            refreshed.push(*reserve_key);
+        }
+        instructions.push(refresh_obligation(
+            program_id,
+            self.obligation,
+            reserve_keys,
+        ));
+        instructions.push(liquidate_obligation(
+            program_id,
+            self.liquidation.repay_amount,
+            accounts.source_liquidity,
+            accounts.destination_collateral,
+            self.repay_reserve,
+            repay_reserve.liquidity.supply_pubkey,
+            self.withdraw_reserve,
+            withdraw_reserve.collateral.supply_pubkey,
+            self.obligation,
+            obligation.lending_market,
+            accounts.user_transfer_authority,
+        ));
+        Ok(instructions)
+    }
+}
+
+/// returns true if the refreshed obligation can be liquidated
+pub fn is_liquidatable(obligation: &LendingObligation) -> bool {
+    obligation.borrowed_value > Decimal::zero()
+        && obligation.borrowed_value >= obligation.unhealthy_borrow_value
+}
+
+/// refreshes a copy of the obligation against `reserves`, and if it is unhealthy returns
+/// the repay and withdraw reserve pair which pays out the largest liquidation bonus
+pub fn find_liquidation_opportunity(
+    obligation_key: Pubkey,
+    obligation: &LendingObligation,
+    reserves: &HashMap<Pubkey, Reserve>,
+) -> Result<Option<LiquidationOpportunity>, ProgramError> {
+    let mut obligation = obligation.clone();
+    pseudo_refresh_lending_obligation(&mut obligation, reserves)?;
+    if !is_liquidatable(&obligation) {
+        return Ok(None);
+    }
+
+    let mut best: Option<LiquidationOpportunity> = None;
+    for liquidity in obligation.borrows.iter() {
+        if liquidity.market_value == Decimal::zero() {
+            continue;
+        }
+        let repay_reserve = find_reserve(reserves, &liquidity.borrow_reserve)?;
+        for collateral in obligation.deposits.iter() {
+            if collateral.deposited_amount == 0 {
+                continue;
+            }
+            let withdraw_reserve = find_reserve(reserves, &collateral.deposit_reserve)?;
+            let liquidation = withdraw_reserve.calculate_lending_obligation_liquidation(
+                u64::MAX,
+                &obligation,
+                liquidity,
+                collateral,
+            )?;
+            let repay_value = market_value(repay_reserve, liquidation.repay_amount)?;
+            let withdraw_value = market_value(
+                withdraw_reserve,
+                withdraw_reserve
+                    .collateral_exchange_rate()?
+                    .collateral_to_liquidity(liquidation.withdraw_amount)?,
+            )?;
+            let bonus_value = withdraw_value.try_sub(repay_value).unwrap_or_default();
+            let is_best = match &best {
+                Some(best) => bonus_value > best.bonus_value,
+                None => true,
+            };
+            if is_best {
+                best = Some(LiquidationOpportunity {
+                    obligation: obligation_key,
+                    repay_reserve: liquidity.borrow_reserve,
+                    withdraw_reserve: collateral.deposit_reserve,
+                    liquidation,
+                    repay_value,
+                    withdraw_value,
+                    bonus_value,
+                });
+            }
+        }
+    }
+    Ok(best)
+}
+
+/// scans the obligations for ones that can be liquidated, returning an opportunity
+/// for each of them ranked by bonus value, largest first
+pub fn find_liquidation_opportunities<'a>(
+    obligations: impl IntoIterator<Item = (&'a Pubkey, &'a LendingObligation)>,
+    reserves: &HashMap<Pubkey, Reserve>,
+) -> Result<Vec<LiquidationOpportunity>, ProgramError> {
+    let mut opportunities = Vec::new();
+    for (obligation_key, obligation) in obligations {
+        if let Some(opportunity) =
+            find_liquidation_opportunity(*obligation_key, obligation, reserves)?
+        {
+            opportunities.push(opportunity);
+        }
+    }
+    opportunities.sort_by_key(|opportunity| Reverse(opportunity.bonus_value));
+    Ok(opportunities)
+}
+
+fn find_reserve<'a>(
+    reserves: &'a HashMap<Pubkey, Reserve>,
+    reserve_key: &Pubkey,
+) -> Result<&'a Reserve, ProgramError> {
+    match reserves.get(reserve_key) {
+        Some(reserve) => Ok(reserve),
+        None => {
+            msg!("failed to find reserve {}", reserve_key);
+            Err(ProgramError::InvalidAccountData)
+        }
+    }
+}
+
+/// market value of a liquidity amount in quote currency
+fn market_value(reserve: &Reserve, liquidity_amount: u64) -> Result<Decimal, ProgramError> {
+    let decimals = 10u64
+        .checked_pow(reserve.liquidity.mint_decimals as u32)
+        .ok_or(LendingError::MathOverflow)?;
+    Decimal::from(liquidity_amount)
+        .try_mul(reserve.liquidity.market_price)?
+        .try_div(decimals)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tulipv2_sdk_common::lending::{
+        lending_obligation::{LendingObligationCollateral, LendingObligationLiquidity},
+        reserve::{ReserveCollateral, ReserveConfig, ReserveLiquidity},
+    };
+
+    fn mock_reserve(market_price: u64, liquidation_bonus: u8) -> Reserve {
+        Reserve {
+            liquidity: ReserveLiquidity {
+                supply_pubkey: Pubkey::new_unique(),
+                oracle_pubkey: Pubkey::new_unique(),
+                available_amount: 1_000_000,
+                cumulative_borrow_rate_wads: Decimal::one(),
+                market_price: Decimal::from(market_price),
+                ..Default::default()
+            },
+            collateral: ReserveCollateral {
+                supply_pubkey: Pubkey::new_unique(),
+                mint_total_supply: 1_000_000,
+                ..Default::default()
+            },
+            config: ReserveConfig {
+                loan_to_value_ratio: 75,
+                liquidation_threshold: 80,
+                liquidation_bonus,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn mock_obligation(deposits: &[(Pubkey, u64)], borrows: &[(Pubkey, u64)]) -> LendingObligation {
+        LendingObligation {
+            lending_market: Pubkey::new_unique(),
+            deposits: deposits
+                .iter()
+                .map(
+                    |(deposit_reserve, deposited_amount)| LendingObligationCollateral {
+                        deposit_reserve: *deposit_reserve,
+                        deposited_amount: *deposited_amount,
+                        ..Default::default()
+                    },
+                )
+                .collect(),
+            borrows: borrows
+                .iter()
+                .map(|(borrow_reserve, amount)| LendingObligationLiquidity {
+                    borrow_reserve: *borrow_reserve,
+                    cumulative_borrow_rate_wads: Decimal::one(),
+                    borrowed_amount_wads: Decimal::from(*amount),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_find_liquidation_opportunities() {
+        let (usdc, sol, ray) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let mut reserves = HashMap::new();
+        reserves.insert(usdc, mock_reserve(1, 5));
+        reserves.insert(sol, mock_reserve(1, 10));
+        reserves.insert(ray, mock_reserve(1, 2));
+
+        let healthy = mock_obligation(&[(sol, 1_000)], &[(usdc, 500)]);
+        let small = mock_obligation(&[(sol, 1_000)], &[(usdc, 900)]);
+        let large = mock_obligation(&[(sol, 5_000), (ray, 5_000)], &[(usdc, 9_000)]);
+        let mut obligations = HashMap::new();
+        let (healthy_key, small_key, large_key) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        obligations.insert(healthy_key, healthy);
+        obligations.insert(small_key, small);
+        obligations.insert(large_key, large.clone());
+
+        let opportunities = find_liquidation_opportunities(&obligations, &reserves).unwrap();
+        assert_eq!(opportunities.len(), 2);
+        assert_eq!(opportunities[0].obligation, large_key);
+        assert_eq!(opportunities[1].obligation, small_key);
+        assert!(opportunities[0].bonus_value > opportunities[1].bonus_value);
+
+        // half the borrow is closed out, withdrawing the collateral with the largest bonus
+        let opportunity = &opportunities[0];
+        assert_eq!(opportunity.repay_reserve, usdc);
+        assert_eq!(opportunity.withdraw_reserve, sol);
+        assert_eq!(opportunity.liquidation.repay_amount, 4_500);
+        assert_eq!(opportunity.liquidation.withdraw_amount, 4_950);
+        assert_eq!(opportunity.repay_value, Decimal::from(4_500_u64));
+        assert_eq!(opportunity.bonus_value, Decimal::from(450_u64));
+
+        let accounts = LiquidatorAccounts {
+            source_liquidity: Pubkey::new_unique(),
+            destination_collateral: Pubkey::new_unique(),
+            user_transfer_authority: Pubkey::new_unique(),
+        };
+        let program_id = Pubkey::new_unique();
+        let ixs = opportunity
+            .instructions(program_id, &large, &reserves, &accounts)
+            .unwrap();
+        // 3 reserve refreshes, the obligation refresh, and the liquidation
+        assert_eq!(ixs.len(), 5);
+        assert_eq!(ixs[3].accounts[0].pubkey, large_key);
+        assert_eq!(ixs[3].accounts.len(), 5);
+        assert_eq!(ixs[4].accounts[2].pubkey, usdc);
+        assert_eq!(ixs[4].accounts[4].pubkey, sol);
+    }
+
+    #[test]
+    fn test_missing_reserve() {
+        let reserve = Pubkey::new_unique();
+        let mut reserves = HashMap::new();
+        reserves.insert(reserve, mock_reserve(1, 5));
+        let obligation = mock_obligation(&[(reserve, 1_000)], &[(Pubkey::new_unique(), 900)]);
+        assert!(
+            find_liquidation_opportunity(Pubkey::new_unique(), &obligation, &reserves).is_err()
+        );
+    }
+}
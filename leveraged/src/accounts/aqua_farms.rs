@@ -1,10 +1,11 @@
+use crate::helpers::error::ConversionError;
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program;
 use solana_program::pubkey::Pubkey;
 use static_pubkey::static_pubkey;
 use std::str::FromStr;
 
-#[derive(Debug, Clone, Copy, AnchorSerialize, AnchorDeserialize)]
+#[derive(Debug, Clone, Copy, AnchorSerialize, AnchorDeserialize, PartialEq, Eq)]
 pub enum AquaFarms {
     SOLUSDC = 0,
     ORCAUSDC = 1,
@@ -30,9 +31,10 @@ pub enum AquaFarms {
     sRLYSOL = 25,
     GMTUSDC = 26,
 }
-impl From<u8> for AquaFarms {
-    fn from(val: u8) -> AquaFarms {
-        match val {
+impl TryFrom<u8> for AquaFarms {
+    type Error = ConversionError;
+    fn try_from(val: u8) -> std::result::Result<AquaFarms, ConversionError> {
+        Ok(match val {
             0 => AquaFarms::SOLUSDC,
             1 => AquaFarms::ORCAUSDC,
             2 => AquaFarms::USDCUSDT,
@@ -56,20 +58,21 @@ impl From<u8> for AquaFarms {
             20 => AquaFarms::stSOLwUST,
             25 => AquaFarms::sRLYSOL,
             26 => AquaFarms::GMTUSDC,
-            _ => panic!(":ruhroh:"),
-        }
+            _ => return Err(ConversionError::UnknownAquaFarm(val)),
+        })
     }
 }
 
-impl From<&str> for AquaFarms {
-    fn from(val: &str) -> AquaFarms {
-        match val {
+impl TryFrom<&str> for AquaFarms {
+    type Error = ConversionError;
+    fn try_from(val: &str) -> std::result::Result<AquaFarms, ConversionError> {
+        Ok(match val {
             "SOL-USDC" => AquaFarms::SOLUSDC,
             "ORCA-USDC" => AquaFarms::ORCAUSDC,
             "USDT-USDC" => AquaFarms::USDCUSDT,
             "ORCA-SOL" => AquaFarms::ORCASOL,
-            _ => panic!(":ruhroh:"),
-        }
+            _ => return Err(ConversionError::UnknownAquaFarmName),
+        })
     }
 }
 
@@ -1057,9 +1060,10 @@ impl AquaFarms {
     }
 }
 
-impl From<super::Farms> for AquaFarms {
-    fn from(val: crate::accounts::Farms) -> AquaFarms {
-        match val {
+impl TryFrom<super::Farms> for AquaFarms {
+    type Error = ConversionError;
+    fn try_from(val: crate::accounts::Farms) -> std::result::Result<AquaFarms, ConversionError> {
+        Ok(match val {
             crate::accounts::Farms::OrcaSolVault => AquaFarms::ORCASOL,
             crate::accounts::Farms::OrcaUsdcVault => AquaFarms::ORCAUSDC,
             crate::accounts::Farms::SolUsdcOrcaVault => AquaFarms::SOLUSDC,
@@ -1079,7 +1083,7 @@ impl From<super::Farms> for AquaFarms {
             crate::accounts::Farms::stSolwUstOrcaVault => AquaFarms::stSOLwUST,
             crate::accounts::Farms::sRlySolOrcaVault => AquaFarms::sRLYSOL,
             crate::accounts::Farms::GmtUsdcOrcaVault => AquaFarms::GMTUSDC,
-            _ => panic!("farm is not an orca farm"),
-        }
+            _ => return Err(ConversionError::NotAnAquaFarm(val)),
+        })
     }
 }
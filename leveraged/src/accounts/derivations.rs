@@ -103,3 +103,19 @@ pub fn derive_orca_vault_user_address(
 ) -> (Pubkey, u8) {
     Pubkey::find_program_address(&[vault.as_ref(), authority.as_ref()], program_id)
 }
+
+/// derives the aquafarm user farm of `owner`, which is the vault pda for orca vaults
+pub fn derive_orca_user_farm_address(
+    global_farm: &Pubkey,
+    owner: &Pubkey,
+    aqua_farm_program: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            global_farm.as_ref(),
+            owner.as_ref(),
+            spl_token::id().as_ref(),
+        ],
+        aqua_farm_program,
+    )
+}
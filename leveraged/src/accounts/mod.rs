@@ -4,6 +4,7 @@ pub mod leveraged_farm;
 pub mod position_info;
 pub mod user_farm;
 
+use crate::helpers::error::ConversionError;
 use anchor_lang::prelude::*;
 
 pub const POSITION_INFO_ACCOUNT_SIZE: usize = 300;
@@ -74,9 +75,10 @@ pub enum Position {
     RemovedLiquidityForLiquidation,
 }
 
-impl From<u8> for Position {
-    fn from(i: u8) -> Position {
-        match i {
+impl TryFrom<u8> for Position {
+    type Error = ConversionError;
+    fn try_from(i: u8) -> std::result::Result<Position, ConversionError> {
+        Ok(match i {
             0 => Position::Opening,
             1 => Position::Swapped,
             2 => Position::AddedLiquidity,
@@ -102,40 +104,61 @@ impl From<u8> for Position {
             22 => Position::DDPulledForLiquidation,
             23 => Position::LPPulledForLiquidation,
             24 => Position::RemovedLiquidityForLiquidation,
-            _ => panic!("invalid index"),
-        }
+            _ => return Err(ConversionError::UnknownPosition(i as u64)),
+        })
     }
 }
 
-impl From<u64> for Position {
-    fn from(i: u64) -> Position {
-        match i {
-            0 => Position::Opening,
-            1 => Position::Swapped,
-            2 => Position::AddedLiquidity,
-            3 => Position::Opened,
-            4 => Position::Withdrawing,
-            5 => Position::RemovedLiquidity,
-            6 => Position::SwappedForRepaying,
-            7 => Position::Repaid,
-            8 => Position::Withdrawn,
-            9 => Position::Closing,
-            10 => Position::ClosingAndExiting,
-            11 => Position::Closed,
-            12 => Position::ExitingAndLiquidated,
-            13 => Position::Liquidated,
-            14 => Position::TopUp,
-            15 => Position::Borrowed,
-            16 => Position::TopUpSwapped,
-            17 => Position::TopUpAddedLiquidity,
-            18 => Position::DepositedOrcaAquaFarm,
-            19 => Position::WithdrawnOrcaDoubleDip,
-            20 => Position::SwappedForLiquidation,
-            21 => Position::RepaidForLiquidation,
-            22 => Position::DDPulledForLiquidation,
-            23 => Position::LPPulledForLiquidation,
-            24 => Position::RemovedLiquidityForLiquidation,
-            _ => panic!("invalid index"),
+impl TryFrom<u64> for Position {
+    type Error = ConversionError;
+    fn try_from(i: u64) -> std::result::Result<Position, ConversionError> {
+        u8::try_from(i)
+            .map_err(|_| ConversionError::UnknownPosition(i))
+            .and_then(Position::try_from)
+    }
+}
+
+impl Position {
+    /// returns the states the next step of the open, top up, close or liquidation flow
+    /// moves an obligation to, following the steps described on each variant.
+    ///
+    /// the leveraged farm program doesn't publish its state machine, so these are the
+    /// states the sdk expects next rather than every transition the program accepts
+    pub fn next_states(&self) -> &'static [Position] {
+        match self {
+            Position::Opening => &[Position::Swapped, Position::AddedLiquidity],
+            Position::Swapped => &[Position::AddedLiquidity],
+            Position::AddedLiquidity => &[Position::Opened, Position::DepositedOrcaAquaFarm],
+            Position::DepositedOrcaAquaFarm => &[Position::Opened],
+            Position::Opened => &[
+                Position::Withdrawing,
+                Position::WithdrawnOrcaDoubleDip,
+                Position::TopUp,
+                Position::Closing,
+            ],
+            Position::TopUp => &[Position::Borrowed, Position::TopUpSwapped],
+            Position::Borrowed => &[Position::TopUpSwapped],
+            Position::TopUpSwapped => &[Position::TopUpAddedLiquidity],
+            Position::TopUpAddedLiquidity => &[Position::Opened],
+            Position::WithdrawnOrcaDoubleDip => &[Position::Withdrawing],
+            Position::Withdrawing => &[Position::RemovedLiquidity],
+            Position::RemovedLiquidity => &[Position::SwappedForRepaying],
+            Position::SwappedForRepaying => &[Position::Repaid],
+            Position::Repaid => &[Position::Withdrawn],
+            Position::Withdrawn => &[Position::Opened, Position::Closed],
+            Position::Closing => &[
+                Position::ClosingAndExiting,
+                Position::DDPulledForLiquidation,
+                Position::LPPulledForLiquidation,
+            ],
+            Position::DDPulledForLiquidation => &[Position::LPPulledForLiquidation],
+            Position::LPPulledForLiquidation => &[Position::RemovedLiquidityForLiquidation],
+            Position::RemovedLiquidityForLiquidation => &[Position::SwappedForLiquidation],
+            Position::SwappedForLiquidation => &[Position::RepaidForLiquidation],
+            Position::RepaidForLiquidation => &[Position::Opened, Position::Liquidated],
+            Position::ClosingAndExiting => &[Position::ExitingAndLiquidated],
+            Position::ExitingAndLiquidated => &[Position::Liquidated],
+            Position::Closed | Position::Liquidated => &[Position::Opening],
         }
     }
 }
@@ -224,9 +247,10 @@ pub enum Farms {
     Unknown = 255,
 }
 
-impl From<u64> for Farms {
-    fn from(num: u64) -> Farms {
-        match num {
+impl TryFrom<u64> for Farms {
+    type Error = ConversionError;
+    fn try_from(num: u64) -> std::result::Result<Farms, ConversionError> {
+        Ok(match num {
             0 => Farms::RayUsdcVault,
             1 => Farms::RaySolVault,
             2 => Farms::RayUsdtVault,
@@ -303,14 +327,16 @@ impl From<u64> for Farms {
             73 => Farms::HawkUsdcRayVault,
             74 => Farms::GmtUsdcOrcaVault,
             75 => Farms::RaywhEthRayVault,
-            _ => Farms::Unknown,
-        }
+            255 => Farms::Unknown,
+            _ => return Err(ConversionError::UnknownFarm(num)),
+        })
     }
 }
 
-impl From<&str> for Farms {
-    fn from(s: &str) -> Farms {
-        match s {
+impl TryFrom<&str> for Farms {
+    type Error = ConversionError;
+    fn try_from(s: &str) -> std::result::Result<Farms, ConversionError> {
+        Ok(match s {
             "RAY-USDC" => Farms::RayUsdcVault,
             "RAY-SOL" => Farms::RaySolVault,
             "RAY-USDT" => Farms::RayUsdtVault,
@@ -387,8 +413,8 @@ impl From<&str> for Farms {
             "HAWK-USDC-RAY" => Farms::HawkUsdcRayVault,
             "GMT-USDC-ORCA" => Farms::GmtUsdcOrcaVault,
             "RAY-whETH-RAY" => Farms::RaywhEthRayVault,
-            _ => Farms::Unknown,
-        }
+            _ => return Err(ConversionError::UnknownFarmName),
+        })
     }
 }
 
@@ -499,3 +525,50 @@ impl Farms {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::aqua_farms::AquaFarms;
+    use super::*;
+
+    #[test]
+    fn test_conversions() {
+        for state in 0..=24_u8 {
+            assert_eq!(Position::try_from(state).unwrap() as u8, state);
+            assert_eq!(Position::try_from(state as u64), Position::try_from(state));
+        }
+        assert_eq!(
+            Position::try_from(25_u8),
+            Err(ConversionError::UnknownPosition(25))
+        );
+        assert_eq!(
+            Position::try_from(256_u64),
+            Err(ConversionError::UnknownPosition(256))
+        );
+        assert_eq!(Farms::try_from(75).unwrap(), Farms::RaywhEthRayVault);
+        assert_eq!(Farms::try_from(255).unwrap(), Farms::Unknown);
+        assert_eq!(Farms::try_from(76), Err(ConversionError::UnknownFarm(76)));
+        assert_eq!(Farms::try_from("ORCA-USDC"), Ok(Farms::OrcaUsdcVault));
+        assert_eq!(
+            Farms::try_from("ORCA-USDT"),
+            Err(ConversionError::UnknownFarmName)
+        );
+        assert_eq!(
+            AquaFarms::try_from("ORCA-SOL").map(|farm| farm as u8),
+            Ok(AquaFarms::ORCASOL as u8)
+        );
+        assert_eq!(
+            AquaFarms::try_from("ORCA-USDT").map(|farm| farm as u8),
+            Err(ConversionError::UnknownAquaFarmName)
+        );
+        assert!(AquaFarms::try_from(Farms::OrcaUsdcVault).is_ok());
+        assert_eq!(
+            AquaFarms::try_from(Farms::RayUsdcVault).map(|farm| farm as u8),
+            Err(ConversionError::NotAnAquaFarm(Farms::RayUsdcVault))
+        );
+        assert_eq!(
+            AquaFarms::try_from(14).map(|farm| farm as u8),
+            Err(ConversionError::UnknownAquaFarm(14))
+        );
+    }
+}
@@ -1,5 +1,7 @@
-use super::Position;
+use super::{Position, USER_FARM_ACCOUNT_SIZE};
+use crate::helpers::error::{ConversionError, UserFarmError};
 use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
 
 /// represents a collection of olibgations managed by this particular UserFarm account
 #[account(zero_copy)]
@@ -38,5 +40,77 @@ pub struct Obligation {
     pub coin_amount: u64,
     pub pc_amount: u64,
     pub deposited_lp_tokens: u64,
-    pub position_state: Position,
+    /// the obligation's `Position`, kept as the raw byte so that states added by newer
+    /// versions of the program can be decoded, see `Obligation::state`
+    pub position_state: u8,
+}
+
+/// size of a packed `Obligation` within the user farm account
+const OBLIGATION_SIZE: usize = 57;
+/// offset of the first obligation, including the discriminator
+const OBLIGATIONS_OFFSET: usize = 76;
+
+impl UserFarm {
+    /// decodes a user farm from its account data.
+    ///
+    /// user farm accounts are stored packed, so the data is read field by field
+    /// instead of being cast into the `repr(C)` struct
+    pub fn try_from_account_data(data: &[u8]) -> std::result::Result<UserFarm, UserFarmError> {
+        if data.len() < USER_FARM_ACCOUNT_SIZE {
+            return Err(UserFarmError::InvalidLength(data.len()));
+        }
+        if data[..8] != UserFarm::discriminator() {
+            return Err(UserFarmError::InvalidDiscriminator);
+        }
+        let mut obligations = [Obligation {
+            obligation_account: Pubkey::default(),
+            coin_amount: 0,
+            pc_amount: 0,
+            deposited_lp_tokens: 0,
+            position_state: Position::Opening as u8,
+        }; 3];
+        for (index, obligation) in obligations.iter_mut().enumerate() {
+            let offset = OBLIGATIONS_OFFSET + index * OBLIGATION_SIZE;
+            *obligation = Obligation::unpack(&data[offset..offset + OBLIGATION_SIZE]);
+        }
+        Ok(UserFarm {
+            authority: read_pubkey(&data[8..40]),
+            leveraged_farm: read_pubkey(&data[40..72]),
+            user_farm_number: data[72],
+            number_of_obligations: data[73],
+            number_of_user_farms: data[74],
+            nonce: data[75],
+            obligations,
+        })
+    }
+}
+
+impl Obligation {
+    /// returns the obligation's position state, or `ConversionError::UnknownPosition`
+    /// for a state unknown to this version of the sdk
+    pub fn state(&self) -> std::result::Result<Position, ConversionError> {
+        Position::try_from(self.position_state)
+    }
+
+    fn unpack(data: &[u8]) -> Obligation {
+        Obligation {
+            obligation_account: read_pubkey(&data[..32]),
+            coin_amount: read_u64(&data[32..40]),
+            pc_amount: read_u64(&data[40..48]),
+            deposited_lp_tokens: read_u64(&data[48..56]),
+            position_state: data[56],
+        }
+    }
+}
+
+fn read_pubkey(data: &[u8]) -> Pubkey {
+    let mut bytes = [0_u8; 32];
+    bytes.copy_from_slice(data);
+    Pubkey::new_from_array(bytes)
+}
+
+fn read_u64(data: &[u8]) -> u64 {
+    let mut bytes = [0_u8; 8];
+    bytes.copy_from_slice(data);
+    u64::from_le_bytes(bytes)
 }
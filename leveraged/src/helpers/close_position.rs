@@ -0,0 +1,350 @@
+//! plans the instructions needed to close all, or part of, a leveraged farm position.
+//!
+//! raydium farms are withdrawn with `withdraw_raydium_vault_close`, while orca farms are
+//! withdrawn with `withdraw_orca_vault_close`, preceded by `withdraw_orca_vault_dd_close`
+//! for double dip aquafarms. the withdrawal records `withdraw_percent` and `close_method`
+//! in the position info account, which the leveraged farm program uses to settle the
+//! withdrawn lp tokens and repay the obligation.
+//!
+//! once a full close has been settled and the position is closed, the position info
+//! account can be closed with `close_position_info_account` to reclaim its rent.
+
+use super::error::PlannerError;
+use super::farm_accounts::{OrcaVaultAccounts, RaydiumVaultAccounts, VaultAccounts};
+use super::position_accounts::PositionAccounts;
+use super::*;
+use crate::accounts::{aqua_farms::AquaFarms, derivations::derive_orca_user_farm_address};
+use tulipv2_sdk_common::config::ORCA_AQUAFARM_PROGRAM;
+
+/// withdraw percent which closes the entire position
+pub const FULL_CLOSE_PERCENT: u8 = 100;
+
+/// the instructions used to close a position
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClosePlan {
+    /// withdraws `withdraw_percent` of the position's lp tokens from the vault, in order
+    pub withdraw: Vec<Instruction>,
+    /// closes the position info account, only present for full closes. it must be sent
+    /// after the leveraged farm program has settled the position
+    pub close_position_info: Option<Instruction>,
+}
+
+/// returns the instructions needed to close `withdraw_percent` of the position held by the
+/// obligation at `obligation_index` of the authority's user farm at `user_farm_index`
+pub fn new_close_position_plan(
+    authority: Pubkey,
+    farm: Farms,
+    user_farm_index: u64,
+    obligation_index: u8,
+    withdraw_percent: u8,
+    close_method: u8,
+) -> std::result::Result<ClosePlan, PlannerError> {
+    ClosePositionPlanner::new(authority, farm, user_farm_index, obligation_index)?
+        .instructions(withdraw_percent, close_method)
+}
+
+/// plans the instructions needed to close a position held by a single obligation of a user farm
+pub struct ClosePositionPlanner {
+    pub position: PositionAccounts,
+}
+
+impl ClosePositionPlanner {
+    pub fn new(
+        authority: Pubkey,
+        farm: Farms,
+        user_farm_index: u64,
+        obligation_index: u8,
+    ) -> std::result::Result<Self, PlannerError> {
+        Ok(Self {
+            position: PositionAccounts::new(authority, farm, user_farm_index, obligation_index)?,
+        })
+    }
+
+    /// returns the instructions used to withdraw `withdraw_percent`, between 1 and 100, of the
+    /// position. `close_method` is stored in the position info account
+    pub fn instructions(
+        &self,
+        withdraw_percent: u8,
+        close_method: u8,
+    ) -> std::result::Result<ClosePlan, PlannerError> {
+        if withdraw_percent == 0 || withdraw_percent > FULL_CLOSE_PERCENT {
+            return Err(PlannerError::InvalidWithdrawPercent(withdraw_percent));
+        }
+        let withdraw = match self.position.accounts.vault {
+            VaultAccounts::Raydium(vault) => {
+                vec![self.withdraw_raydium_vault_ix(&vault, withdraw_percent, close_method)?]
+            }
+            VaultAccounts::Orca(vault) => {
+                let mut instructions = Vec::with_capacity(2);
+                if AquaFarms::try_from(self.position.farm)?.is_double_dip() {
+                    instructions.push(self.withdraw_orca_vault_dd_ix(
+                        &vault,
+                        withdraw_percent,
+                        close_method,
+                    )?);
+                }
+                instructions.push(self.withdraw_orca_vault_ix(
+                    &vault,
+                    withdraw_percent,
+                    close_method,
+                )?);
+                instructions
+            }
+        };
+        let close_position_info = if withdraw_percent == FULL_CLOSE_PERCENT {
+            Some(self.close_position_info_ix()?)
+        } else {
+            None
+        };
+        Ok(ClosePlan {
+            withdraw,
+            close_position_info,
+        })
+    }
+
+    /// withdraws the obligation vault's lp tokens from the raydium vault
+    pub fn withdraw_raydium_vault_ix(
+        &self,
+        vault: &RaydiumVaultAccounts,
+        withdraw_percent: u8,
+        close_method: u8,
+    ) -> std::result::Result<Instruction, PlannerError> {
+        let stake_pool = vault
+            .stake_pool
+            .ok_or(PlannerError::MissingStakePool(self.position.farm))?;
+        let vault_program = self.position.config.solfarm_vault_program;
+        let (user_balance_account, user_balance_meta) =
+            vault.user_balance_accounts(&vault_program, &self.position.obligation_vault);
+        new_withdraw_raydium_vault_ix(
+            Box::new(withdraw_raydium_vault_close::WithdrawFarm {
+                authority: self.position.authority,
+                user_farm: self.position.user_farm,
+                obligation_vault_address: self.position.obligation_vault,
+                leveraged_farm: self.position.config.account,
+                authority_token_account: self.position.obligation_vault_lp_account(),
+                vault: self.position.config.vault_account,
+                vault_program,
+                user_balance_account,
+                user_info_account: vault.vault_info_account,
+                user_lp_token_account: vault.lp_token_account,
+                user_reward_a_token_account: vault.reward_a_token_account,
+                pool_reward_a_token_account: vault.pool_reward_a_token_account,
+                user_reward_b_token_account: vault.reward_b_token_account,
+                pool_reward_b_token_account: vault.pool_reward_b_token_account,
+                token_program_id: spl_token::id(),
+                clock: sysvar::clock::id(),
+                vault_pda_account: self.position.accounts.vault_pda,
+                pool_lp_token_account: stake_pool.pool_lp_token_account,
+                pool_authority: vault.pool_authority,
+                pool_id: stake_pool.pool_id,
+                stake_program_id: stake_pool.stake_program,
+                user_balance_meta,
+            }),
+            self.position.config.lending_market,
+            self.position.user_farm_obligation,
+            self.position.config.lending_market_authority,
+            self.position.config.lending_program,
+            self.position.position_info,
+            system_program::id(),
+            sysvar::rent::id(),
+            self.position.obligation_index,
+            withdraw_percent,
+            close_method,
+            self.position.farm,
+        )
+        .ok_or(PlannerError::InvalidInstruction(
+            "withdraw_raydium_vault_close",
+        ))
+    }
+
+    /// withdraws the obligation vault's lp tokens from the orca vault and its aquafarm
+    pub fn withdraw_orca_vault_ix(
+        &self,
+        vault: &OrcaVaultAccounts,
+        withdraw_percent: u8,
+        close_method: u8,
+    ) -> std::result::Result<Instruction, PlannerError> {
+        let aqua_farm = AquaFarms::try_from(self.position.farm)?;
+        let global_farm = aqua_farm.global_farm();
+        new_withdraw_orca_vault_close_ix(
+            Box::new(withdraw_orca_vault::WithdrawOrcaFarm {
+                authority: self.position.authority,
+                vault_account: self.position.config.vault_account,
+                vault_user_account: self.position.orca_vault_user_account(),
+                token_program: spl_token::id(),
+                rent: sysvar::rent::id(),
+                vault_pda: self.position.accounts.vault_pda,
+                system_program: system_program::id(),
+                user_farm_owner: self.position.accounts.vault_pda,
+                user_transfer_authority: self.position.accounts.vault_pda,
+                user_base_token_account: vault.vault_swap_token_account,
+                user_farm_token_account: vault.vault_farm_token_account,
+                user_reward_token_account: vault.vault_reward_token_account,
+                global_base_token_vault: aqua_farm.global_base_token_vault(),
+                farm_token_mint: aqua_farm.farm_token_mint(),
+                global_farm,
+                orca_user_farm: derive_orca_user_farm_address(
+                    &global_farm,
+                    &self.position.accounts.vault_pda,
+                    &ORCA_AQUAFARM_PROGRAM,
+                )
+                .0,
+                global_reward_token_vault: aqua_farm.global_reward_token_vault(),
+                convert_authority: aqua_farm.convert_authority(),
+                aqua_farm_program: ORCA_AQUAFARM_PROGRAM,
+                receiving_token_account: self.position.obligation_vault_lp_account(),
+                clock: sysvar::clock::id(),
+                leveraged_user_farm: self.position.user_farm,
+                leveraged_farm: self.position.config.account,
+                solfarm_vault_program: self.position.config.solfarm_vault_program,
+                obligation_vault_address: self.position.obligation_vault,
+            }),
+            self.position.config.lending_market,
+            self.position.user_farm_obligation,
+            self.position.config.lending_market_authority,
+            self.position.config.lending_program,
+            self.position.obligation_index,
+            withdraw_percent,
+            close_method,
+        )
+        .ok_or(PlannerError::InvalidInstruction(
+            "withdraw_orca_vault_close",
+        ))
+    }
+
+    /// withdraws the orca vault's farm tokens from the double dip aquafarm
+    pub fn withdraw_orca_vault_dd_ix(
+        &self,
+        vault: &OrcaVaultAccounts,
+        withdraw_percent: u8,
+        close_method: u8,
+    ) -> std::result::Result<Instruction, PlannerError> {
+        let double_dip = vault
+            .double_dip
+            .ok_or(PlannerError::MissingDoubleDipAccounts(self.position.farm))?;
+        let aqua_farm = AquaFarms::try_from(self.position.farm)?;
+        let global_farm_dd = aqua_farm.global_farm_dd();
+        new_withdraw_orca_vault_dd_close_ix(
+            Box::new(withdraw_orca_vault_dd_close::WithdrawOrcaFarmDoubleDip {
+                authority: self.position.authority,
+                vault_account: self.position.config.vault_account,
+                vault_user_account: self.position.orca_vault_user_account(),
+                token_program: spl_token::id(),
+                rent: sysvar::rent::id(),
+                vault_pda: self.position.accounts.vault_pda,
+                system_program: system_program::id(),
+                user_farm_token_account: vault.vault_farm_token_account,
+                user_farm_dd_token_account: double_dip.vault_farm_dd_token_account,
+                user_reward_dd_token_account: double_dip.vault_reward_dd_token_account,
+                global_base_dd_token_vault: aqua_farm.global_base_token_vault_dd(),
+                farm_dd_token_mint: aqua_farm.farm_token_mint_dd(),
+                global_farm_dd,
+                user_farm_dd: derive_orca_user_farm_address(
+                    &global_farm_dd,
+                    &self.position.accounts.vault_pda,
+                    &ORCA_AQUAFARM_PROGRAM,
+                )
+                .0,
+                global_reward_dd_token_vault: aqua_farm.global_reward_token_vault_dd(),
+                convert_authority_dd: aqua_farm.convert_authority_dd(),
+                aqua_farm_program: ORCA_AQUAFARM_PROGRAM,
+                leveraged_user_farm: self.position.user_farm,
+                leveraged_farm: self.position.config.account,
+                solfarm_vault_program: self.position.config.solfarm_vault_program,
+                obligation_vault_address: self.position.obligation_vault,
+            }),
+            self.position.config.lending_market,
+            self.position.user_farm_obligation,
+            self.position.config.lending_market_authority,
+            self.position.config.lending_program,
+            self.position.position_info,
+            self.position.obligation_index,
+            withdraw_percent,
+            close_method,
+        )
+        .ok_or(PlannerError::InvalidInstruction(
+            "withdraw_orca_vault_dd_close",
+        ))
+    }
+
+    /// closes the position info account of a closed position
+    pub fn close_position_info_ix(&self) -> std::result::Result<Instruction, PlannerError> {
+        new_close_position_info_account_ix(self.position.authority, self.position.position_info)
+            .ok_or(PlannerError::InvalidInstruction(
+                "close_position_info_account",
+            ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::open_position::MAX_OBLIGATIONS;
+    use super::*;
+
+    #[test]
+    fn test_close_raydium_position() {
+        let authority = Pubkey::new_unique();
+        let plan = new_close_position_plan(authority, Farms::RayUsdcVault, 0, 1, 50, 0).unwrap();
+        assert_eq!(plan.withdraw.len(), 1);
+        assert!(plan.close_position_info.is_none());
+        let ix = &plan.withdraw[0];
+        assert_eq!(ix.accounts[0].pubkey, authority);
+        assert!(ix.accounts[0].is_signer);
+        // obligation index, withdraw percent and close method follow the nonces
+        assert_eq!(ix.data[10..], [1, 50, 0]);
+
+        let planner = ClosePositionPlanner::new(authority, Farms::RayUsdcVault, 0, 0).unwrap();
+        let plan = planner.instructions(FULL_CLOSE_PERCENT, 1).unwrap();
+        let close_position_info = plan.close_position_info.unwrap();
+        assert_eq!(
+            close_position_info.accounts[1].pubkey,
+            planner.position.position_info
+        );
+        assert_eq!(
+            plan.withdraw[0].accounts[plan.withdraw[0].accounts.len() - 3].pubkey,
+            planner.position.position_info
+        );
+
+        for withdraw_percent in [0, FULL_CLOSE_PERCENT + 1] {
+            assert_eq!(
+                planner.instructions(withdraw_percent, 0),
+                Err(PlannerError::InvalidWithdrawPercent(withdraw_percent))
+            );
+        }
+        assert_eq!(
+            new_close_position_plan(authority, Farms::RaySrmVault, 0, 0, 100, 0),
+            Err(PlannerError::MissingStakePool(Farms::RaySrmVault))
+        );
+        assert_eq!(
+            ClosePositionPlanner::new(authority, Farms::RayUsdcVault, 0, MAX_OBLIGATIONS).err(),
+            Some(PlannerError::InvalidObligationIndex(MAX_OBLIGATIONS))
+        );
+    }
+
+    #[test]
+    fn test_close_orca_position() {
+        use tulipv2_sdk_common::config::levfarm::orca_orcausdc::vault_config;
+
+        let authority = Pubkey::new_unique();
+        let plan = new_close_position_plan(authority, Farms::OrcaUsdcVault, 0, 0, 100, 2).unwrap();
+        // ORCA-USDC is not a double dip farm
+        assert_eq!(plan.withdraw.len(), 1);
+        assert!(plan.close_position_info.is_some());
+        let accounts = &plan.withdraw[0].accounts;
+        assert_eq!(
+            accounts[12].pubkey,
+            vault_config::AQUA_FARM_BASE_TOKEN_VAULT
+        );
+        assert_eq!(accounts[13].pubkey, vault_config::FARM_TOKEN_MINT);
+        assert_eq!(
+            accounts[16].pubkey,
+            vault_config::AQUA_FARM_REWARD_TOKEN_VAULT
+        );
+        assert_eq!(
+            new_close_position_plan(authority, Farms::OrcaSolVault, 0, 0, 100, 2),
+            Err(PlannerError::Config(LevFarmConfigError::MissingConfig(
+                Farms::OrcaSolVault
+            )))
+        );
+    }
+}
@@ -0,0 +1,114 @@
+//! Error types
+
+use crate::accounts::{aqua_farms::AquaFarms, Farms, Position};
+use anchor_lang::prelude::Pubkey;
+use thiserror::Error;
+use tulipv2_sdk_farms::Farm;
+
+/// Errors that may be returned when looking up the configuration of a leveraged farm
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
+pub enum LevFarmConfigError {
+    #[error("{0:?} requires the {1} feature to be activated")]
+    FeatureDisabled(Farms, &'static str),
+    #[error("No leveraged farm configuration is available for {0:?}")]
+    MissingConfig(Farms),
+    #[error("{0:?} is not a raydium farm, and has no vault info account")]
+    NoVaultInfoAccount(Farms),
+}
+
+/// Errors that may be returned when planning the instructions of a leveraged farm position
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
+pub enum PlannerError {
+    #[error(transparent)]
+    Config(#[from] LevFarmConfigError),
+    #[error(transparent)]
+    Conversion(#[from] ConversionError),
+    #[error("No raydium staking pool is configured for {0:?}")]
+    MissingStakePool(Farms),
+    #[error("Obligation index {0} is invalid, a user farm manages at most 3 obligations")]
+    InvalidObligationIndex(u8),
+    #[error("No double dip vault accounts are configured for {0:?}")]
+    MissingDoubleDipAccounts(Farms),
+    #[error("Withdraw percent {0} is invalid, it must be between 1 and 100")]
+    InvalidWithdrawPercent(u8),
+    #[error("A position in the {state:?} state cannot be resumed, its next states are {next:?}")]
+    CannotResume {
+        state: Position,
+        next: &'static [Position],
+    },
+    #[error("Resuming a close from the {state:?} state requires instructions this sdk can't build yet, its next states are {next:?}")]
+    UnsupportedCloseStep {
+        state: Position,
+        next: &'static [Position],
+    },
+    #[error("Obligation {0} holds no position to resume")]
+    NoPosition(u8),
+    #[error("Leverage must be at least 1")]
+    InvalidLeverage,
+    #[error("Leverage exceeds the maximum loan to value ratio of the reserves")]
+    LeverageTooHigh,
+    #[error("The reserve of {0} cannot supply the liquidity to be borrowed")]
+    InsufficientLiquidity(Pubkey),
+    #[error("The orca swap accounts of {0:?} are unknown to the sdk")]
+    UnknownSwapAccounts(Farms),
+    #[error("Failed to build the {0} instruction")]
+    InvalidInstruction(&'static str),
+    #[error("Math overflow")]
+    MathOverflow,
+}
+
+/// Errors that may be returned when quoting a swap against an amm pool
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
+pub enum SwapQuoteError {
+    #[error("Failed to decode the pool's token account")]
+    InvalidTokenAccount,
+    #[error("The pool has no liquidity")]
+    EmptyPool,
+    #[error("The pool's fee numerator must not exceed its non-zero denominator")]
+    InvalidFees,
+    #[error("Slippage of {0}/{1} is invalid")]
+    InvalidSlippage(u64, u64),
+    #[error("Math overflow")]
+    MathOverflow,
+}
+
+/// Errors that may be returned when converting raw values into farm or position enums
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
+pub enum ConversionError {
+    #[error("Unknown position state {0}")]
+    UnknownPosition(u64),
+    #[error("Unknown farm {0}")]
+    UnknownFarm(u64),
+    #[error("Unknown aquafarm {0}")]
+    UnknownAquaFarm(u8),
+    #[error("Unknown farm name")]
+    UnknownFarmName,
+    #[error("Unknown aquafarm name")]
+    UnknownAquaFarmName,
+    #[error("{0:?} is not an aquafarm")]
+    NotAnAquaFarm(Farms),
+    #[error("{0:?} has no matching v2 vault farm")]
+    NoV2Farm(Farms),
+    #[error("{0:?} has no matching leveraged farm")]
+    NoLeveragedFarm(Farm),
+    #[error("{0:?} has no matching leveraged farm")]
+    NoLeveragedAquaFarm(AquaFarms),
+}
+
+/// Errors that may be returned when decoding a user farm account
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
+pub enum UserFarmError {
+    #[error("User farm account data of {0} bytes is too short")]
+    InvalidLength(usize),
+    #[error("Account is not a user farm")]
+    InvalidDiscriminator,
+}
+
+/// Errors that may be returned when discovering the user farms of an authority
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub enum DiscoveryError<E> {
+    #[error("Failed to fetch account {0}: {1}")]
+    Fetch(Pubkey, E),
+    #[error("Failed to decode user farm {0}: {1}")]
+    UserFarm(Pubkey, UserFarmError),
+}
@@ -0,0 +1,226 @@
+//! accounts used by leveraged farm instructions which are not part of `LevFarmConfig`
+
+use super::error::LevFarmConfigError;
+use crate::accounts::Farms;
+use anchor_lang::prelude::*;
+
+/// accounts of a leveraged farm, its lending reserves and vault, which are
+/// stored in the farm's config module but not in `LevFarmConfig`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FarmAccounts {
+    pub amm_authority: Pubkey,
+    pub serum_vault_signer: Pubkey,
+    /// liquidity supply of the lending reserve for the base token
+    pub base_reserve_liquidity_supply: Pubkey,
+    /// liquidity supply of the lending reserve for the quote token
+    pub quote_reserve_liquidity_supply: Pubkey,
+    pub vault_pda: Pubkey,
+    pub vault: VaultAccounts,
+}
+
+/// accounts specific to the type of vault the farm deposits lp tokens into
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VaultAccounts {
+    Raydium(RaydiumVaultAccounts),
+    Orca(OrcaVaultAccounts),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RaydiumVaultAccounts {
+    pub vault_info_account: Pubkey,
+    pub lp_token_account: Pubkey,
+    pub pool_authority: Pubkey,
+    pub reward_a_token_account: Pubkey,
+    pub pool_reward_a_token_account: Pubkey,
+    pub reward_b_token_account: Pubkey,
+    pub pool_reward_b_token_account: Pubkey,
+    /// the raydium staking pool the vault deposits into, if it is known
+    pub stake_pool: Option<RaydiumStakePool>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RaydiumStakePool {
+    pub stake_program: Pubkey,
+    pub pool_id: Pubkey,
+    pub pool_lp_token_account: Pubkey,
+}
+
+impl RaydiumVaultAccounts {
+    /// derives the vault user balance account of the obligation vault, and its metadata account
+    pub fn user_balance_accounts(
+        &self,
+        vault_program: &Pubkey,
+        obligation_vault: &Pubkey,
+    ) -> (Pubkey, Pubkey) {
+        let user_balance_account = Pubkey::find_program_address(
+            &[self.vault_info_account.as_ref(), obligation_vault.as_ref()],
+            vault_program,
+        )
+        .0;
+        let user_balance_metadata = Pubkey::find_program_address(
+            &[user_balance_account.as_ref(), obligation_vault.as_ref()],
+            vault_program,
+        )
+        .0;
+        (user_balance_account, user_balance_metadata)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OrcaVaultAccounts {
+    pub vault_deposit_queue: Pubkey,
+    /// the vault's token account for the orca pool's lp tokens
+    pub vault_swap_token_account: Pubkey,
+    pub vault_reward_token_account: Pubkey,
+    /// the vault's token account for the aquafarm's farm tokens
+    pub vault_farm_token_account: Pubkey,
+    /// the vault's double dip token accounts, required by double dip aquafarms
+    pub double_dip: Option<OrcaDoubleDipAccounts>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OrcaDoubleDipAccounts {
+    pub vault_farm_dd_token_account: Pubkey,
+    pub vault_reward_dd_token_account: Pubkey,
+}
+
+#[allow(unused_macros)]
+macro_rules! raydium_farm_accounts {
+    // `$stake_pool` may reference the `vault_config` module of the farm
+    ($farm:expr, $module:ident, $stake_pool:expr) => {{
+        use tulipv2_sdk_common::config::levfarm::$module::{
+            market_config::*, reserve_config::*, vault_config,
+        };
+        Ok(FarmAccounts {
+            amm_authority: AMM_AUTHORITY,
+            serum_vault_signer: SERUM_VAULT_SIGNER,
+            base_reserve_liquidity_supply: BASE_RESERVE_LIQUIDITY_SUPPLY,
+            quote_reserve_liquidity_supply: QUOTE_RESERVE_LIQUIDITY_SUPPLY,
+            vault_pda: vault_config::PDA,
+            vault: VaultAccounts::Raydium(RaydiumVaultAccounts {
+                vault_info_account: super::vault_info_account($farm)?,
+                lp_token_account: vault_config::LP_TOKEN_ACCOUNT,
+                pool_authority: vault_config::POOL_AUTHORITY,
+                reward_a_token_account: vault_config::REWARD_A_TOKEN_ACCOUNT,
+                pool_reward_a_token_account: vault_config::POOL_REWARD_A_TOKEN_ACCOUNT,
+                reward_b_token_account: vault_config::REWARD_B_TOKEN_ACCOUNT,
+                pool_reward_b_token_account: vault_config::POOL_REWARD_B_TOKEN_ACCOUNT,
+                stake_pool: $stake_pool,
+            }),
+        })
+    }};
+}
+
+/// returns the accounts of the given leveraged farm which are not part of its `LevFarmConfig`
+pub fn farm_accounts(farm: Farms) -> std::result::Result<FarmAccounts, LevFarmConfigError> {
+    match farm {
+        Farms::RayUsdcVault => {
+            #[cfg(not(feature = "ray-rayusdc-levfarm"))]
+            return Err(LevFarmConfigError::FeatureDisabled(
+                farm,
+                "ray-rayusdc-levfarm",
+            ));
+            #[cfg(feature = "ray-rayusdc-levfarm")]
+            return raydium_farm_accounts!(
+                farm,
+                ray_rayusdc,
+                Some(RaydiumStakePool {
+                    stake_program: tulipv2_sdk_common::config::RAYDIUM_STAKE,
+                    pool_id: vault_config::POOL_ID,
+                    pool_lp_token_account: vault_config::POOL_LP_TOKEN_ACCOUNT,
+                })
+            );
+        }
+        Farms::RaySrmVault => {
+            #[cfg(not(feature = "ray-raysrm-levfarm"))]
+            return Err(LevFarmConfigError::FeatureDisabled(
+                farm,
+                "ray-raysrm-levfarm",
+            ));
+            #[cfg(feature = "ray-raysrm-levfarm")]
+            return raydium_farm_accounts!(farm, ray_raysrm, None);
+        }
+        Farms::RayUsdtVault => {
+            #[cfg(not(feature = "ray-rayusdt-levfarm"))]
+            return Err(LevFarmConfigError::FeatureDisabled(
+                farm,
+                "ray-rayusdt-levfarm",
+            ));
+            #[cfg(feature = "ray-rayusdt-levfarm")]
+            return raydium_farm_accounts!(farm, ray_rayusdt, None);
+        }
+        Farms::SolUsdcRayVault => {
+            #[cfg(not(feature = "ray-solusdc-levfarm"))]
+            return Err(LevFarmConfigError::FeatureDisabled(
+                farm,
+                "ray-solusdc-levfarm",
+            ));
+            #[cfg(feature = "ray-solusdc-levfarm")]
+            return raydium_farm_accounts!(farm, ray_solusdc, None);
+        }
+        Farms::OrcaUsdcVault => {
+            #[cfg(not(feature = "orca-orcausdc-levfarm"))]
+            return Err(LevFarmConfigError::FeatureDisabled(
+                farm,
+                "orca-orcausdc-levfarm",
+            ));
+            #[cfg(feature = "orca-orcausdc-levfarm")]
+            {
+                use spl_associated_token_account::get_associated_token_address;
+                use tulipv2_sdk_common::config::levfarm::orca_orcausdc::{
+                    market_config::*, reserve_config::*, vault_config,
+                };
+                Ok(FarmAccounts {
+                    amm_authority: AMM_AUTHORITY,
+                    serum_vault_signer: SERUM_VAULT_SIGNER,
+                    base_reserve_liquidity_supply: BASE_RESERVE_LIQUIDITY_SUPPLY,
+                    quote_reserve_liquidity_supply: QUOTE_RESERVE_LIQUIDITY_SUPPLY,
+                    vault_pda: vault_config::PDA,
+                    vault: VaultAccounts::Orca(OrcaVaultAccounts {
+                        vault_deposit_queue: vault_config::VAULT_DEPOSIT_QUEUE,
+                        vault_swap_token_account: vault_config::VAULT_SWAP_POOL_TOKEN_ACCOUNT,
+                        vault_reward_token_account: vault_config::VAULT_REWARD_TOKEN_ACCOUNT,
+                        // like its other token accounts, this is the vault pda's associated token account
+                        vault_farm_token_account: get_associated_token_address(
+                            &vault_config::PDA,
+                            &vault_config::FARM_TOKEN_MINT,
+                        ),
+                        double_dip: None,
+                    }),
+                })
+            }
+        }
+        _ => Err(LevFarmConfigError::MissingConfig(farm)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::accounts::aqua_farms::AquaFarms;
+    use crate::helpers::lev_farm_config;
+
+    #[test]
+    fn test_farm_accounts() {
+        for farm in (0..=u8::MAX as u64).filter_map(|farm| Farms::try_from(farm).ok()) {
+            match (lev_farm_config(farm), farm_accounts(farm)) {
+                (Ok(_), Ok(accounts)) => match accounts.vault {
+                    VaultAccounts::Raydium(vault) => {
+                        assert!(!farm.is_aquafarm());
+                        assert_eq!(vault.stake_pool.is_some(), farm == Farms::RayUsdcVault);
+                    }
+                    VaultAccounts::Orca(vault) => {
+                        assert!(farm.is_aquafarm());
+                        assert_eq!(
+                            vault.double_dip.is_some(),
+                            AquaFarms::try_from(farm).unwrap().is_double_dip()
+                        );
+                    }
+                },
+                (Err(config_err), Err(err)) => assert_eq!(config_err, err),
+                _ => panic!("{:?} config and accounts are inconsistent", farm),
+            }
+        }
+    }
+}
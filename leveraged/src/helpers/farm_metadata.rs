@@ -0,0 +1,356 @@
+//! maps leveraged farms to their aquafarms and v2 vault farms, and describes the tokens
+//! each leveraged farm provides liquidity with.
+//!
+//! leveraged farms are identified by `Farms`, orca leveraged farms additionally by the
+//! `AquaFarms` their vault deposits into, and v2 vaults by `tulipv2_sdk_farms::Farm`.
+//! not every leveraged farm has a v2 vault, and not every v2 vault has a leveraged farm.
+
+use super::error::{ConversionError, LevFarmConfigError};
+use super::lev_farm_config;
+use crate::accounts::{aqua_farms::AquaFarms, Farms};
+use anchor_lang::prelude::Pubkey;
+use tulipv2_sdk_farms::{orca::Orca, raydium::Raydium, Farm};
+
+/// the amm a leveraged farm provides liquidity to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Protocol {
+    Raydium,
+    Orca,
+}
+
+/// describes a leveraged farm, its tokens, and the vaults it maps to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FarmMetadata {
+    pub farm: Farms,
+    pub protocol: Protocol,
+    /// the aquafarm of orca leveraged farms
+    pub aqua_farm: Option<AquaFarms>,
+    /// the v2 vault farm for the same lp token, if one exists
+    pub v2_farm: Option<Farm>,
+    pub base_token_mint: Pubkey,
+    pub quote_token_mint: Pubkey,
+    pub lp_mint: Pubkey,
+}
+
+/// leveraged farms and the v2 vault farms of the same lp tokens
+const V2_FARMS: &[(Farms, Farm)] = &[
+    (
+        Farms::AlephUsdcVault,
+        Farm::Raydium {
+            name: Raydium::ALEPHUSDC,
+        },
+    ),
+    (
+        Farms::BopRayVault,
+        Farm::Raydium {
+            name: Raydium::BOPRAY,
+        },
+    ),
+    (
+        Farms::CopeUsdcVault,
+        Farm::Raydium {
+            name: Raydium::COPEUSDC,
+        },
+    ),
+    (
+        Farms::LikeUsdcVault,
+        Farm::Raydium {
+            name: Raydium::LIKEUSDC,
+        },
+    ),
+    (
+        Farms::MerUsdcVault,
+        Farm::Raydium {
+            name: Raydium::MERUSDC,
+        },
+    ),
+    (
+        Farms::stSolUsdcRayVault,
+        Farm::Raydium {
+            name: Raydium::stSOLUSDC,
+        },
+    ),
+    (
+        Farms::RayUsdtVault,
+        Farm::Raydium {
+            name: Raydium::RAYUSDT,
+        },
+    ),
+    (
+        Farms::RayUsdcVault,
+        Farm::Raydium {
+            name: Raydium::RAYUSDC,
+        },
+    ),
+    (
+        Farms::RaySrmVault,
+        Farm::Raydium {
+            name: Raydium::RAYSRM,
+        },
+    ),
+    (
+        Farms::RaySolVault,
+        Farm::Raydium {
+            name: Raydium::RAYSOL,
+        },
+    ),
+    (
+        Farms::RayEthVault,
+        Farm::Raydium {
+            name: Raydium::RAYETH,
+        },
+    ),
+    (
+        Farms::RopeUsdcVault,
+        Farm::Raydium {
+            name: Raydium::ROPEUSDC,
+        },
+    ),
+    (
+        Farms::SamoRayVault,
+        Farm::Raydium {
+            name: Raydium::SAMORAY,
+        },
+    ),
+    (
+        Farms::SnyUsdcVault,
+        Farm::Raydium {
+            name: Raydium::SNYUSDC,
+        },
+    ),
+    (
+        Farms::stSolUsdtRayVault,
+        Farm::Raydium {
+            name: Raydium::stSOLUSDT,
+        },
+    ),
+    (
+        Farms::TulipUsdcVault,
+        Farm::Raydium {
+            name: Raydium::TULIPUSDC,
+        },
+    ),
+    (
+        Farms::AtlasRayVault,
+        Farm::Raydium {
+            name: Raydium::ATLASRAY,
+        },
+    ),
+    (
+        Farms::PolisRayVault,
+        Farm::Raydium {
+            name: Raydium::POLISRAY,
+        },
+    ),
+    (
+        Farms::AtlasUsdcOrcaVault,
+        Farm::Orca {
+            name: Orca::ATLASUSDC,
+        },
+    ),
+    (
+        Farms::PolisUsdcOrcaVault,
+        Farm::Orca {
+            name: Orca::POLISUSDC,
+        },
+    ),
+    (
+        Farms::OrcaSolVault,
+        Farm::Orca {
+            name: Orca::ORCASOL,
+        },
+    ),
+    (
+        Farms::OrcaUsdcVault,
+        Farm::Orca {
+            name: Orca::ORCAUSDC,
+        },
+    ),
+    (
+        Farms::BasisUsdcOrcVault,
+        Farm::Orca {
+            name: Orca::BASISUSDC,
+        },
+    ),
+    (
+        Farms::SamoUsdcOrcaVault,
+        Farm::Orca {
+            name: Orca::SAMOUSDC,
+        },
+    ),
+    (
+        Farms::ShdwUsdcOrcaVault,
+        Farm::Orca {
+            name: Orca::SHDWUSDC,
+        },
+    ),
+    (
+        Farms::ShdwSolOrcaVault,
+        Farm::Orca {
+            name: Orca::SHDWSOL,
+        },
+    ),
+    (
+        Farms::stSolUsdcOrcaVault,
+        Farm::Orca {
+            name: Orca::stSOLUSDC,
+        },
+    ),
+];
+
+impl TryFrom<Farms> for Farm {
+    type Error = ConversionError;
+    fn try_from(farm: Farms) -> std::result::Result<Farm, ConversionError> {
+        V2_FARMS
+            .iter()
+            .find(|(leveraged_farm, _)| *leveraged_farm == farm)
+            .map(|(_, v2_farm)| *v2_farm)
+            .ok_or(ConversionError::NoV2Farm(farm))
+    }
+}
+
+impl TryFrom<Farm> for Farms {
+    type Error = ConversionError;
+    fn try_from(farm: Farm) -> std::result::Result<Farms, ConversionError> {
+        V2_FARMS
+            .iter()
+            .find(|(_, v2_farm)| *v2_farm == farm)
+            .map(|(leveraged_farm, _)| *leveraged_farm)
+            .ok_or(ConversionError::NoLeveragedFarm(farm))
+    }
+}
+
+impl TryFrom<AquaFarms> for Farms {
+    type Error = ConversionError;
+    fn try_from(aqua_farm: AquaFarms) -> std::result::Result<Farms, ConversionError> {
+        (0_u64..)
+            .map_while(|farm| Farms::try_from(farm).ok())
+            .filter(Farms::is_aquafarm)
+            .find(|farm| AquaFarms::try_from(*farm) == Ok(aqua_farm))
+            .ok_or(ConversionError::NoLeveragedAquaFarm(aqua_farm))
+    }
+}
+
+impl TryFrom<AquaFarms> for Farm {
+    type Error = ConversionError;
+    fn try_from(aqua_farm: AquaFarms) -> std::result::Result<Farm, ConversionError> {
+        Farm::try_from(Farms::try_from(aqua_farm)?)
+    }
+}
+
+impl TryFrom<Farm> for AquaFarms {
+    type Error = ConversionError;
+    fn try_from(farm: Farm) -> std::result::Result<AquaFarms, ConversionError> {
+        AquaFarms::try_from(Farms::try_from(farm)?)
+    }
+}
+
+impl Farms {
+    pub fn protocol(&self) -> Protocol {
+        if self.is_aquafarm() {
+            Protocol::Orca
+        } else {
+            Protocol::Raydium
+        }
+    }
+}
+
+/// returns the metadata of the given leveraged farm. the mints of orca farms are those of
+/// their aquafarm, while the mints of raydium farms require their configuration
+pub fn farm_metadata(farm: Farms) -> std::result::Result<FarmMetadata, LevFarmConfigError> {
+    let aqua_farm = AquaFarms::try_from(farm).ok();
+    let (base_token_mint, quote_token_mint, lp_mint) = match aqua_farm {
+        Some(aqua_farm) => (
+            aqua_farm.base_token_mint(),
+            aqua_farm.quote_token_mint(),
+            aqua_farm.swap_pool_mint(),
+        ),
+        None => {
+            let config = lev_farm_config(farm)?;
+            (
+                config.base_token_mint,
+                config.quote_token_mint,
+                config.lp_mint,
+            )
+        }
+    };
+    Ok(FarmMetadata {
+        farm,
+        protocol: farm.protocol(),
+        aqua_farm,
+        v2_farm: Farm::try_from(farm).ok(),
+        base_token_mint,
+        quote_token_mint,
+        lp_mint,
+    })
+}
+
+/// returns the metadata of every leveraged farm whose mints are known
+pub fn farm_metadata_table() -> Vec<FarmMetadata> {
+    (0_u64..)
+        .map_while(|farm| Farms::try_from(farm).ok())
+        .filter_map(|farm| farm_metadata(farm).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_farm_mapping() {
+        for (farm, v2_farm) in V2_FARMS {
+            assert_eq!(Farm::try_from(*farm), Ok(*v2_farm));
+            assert_eq!(Farms::try_from(*v2_farm), Ok(*farm));
+            assert_eq!(farm.is_aquafarm(), matches!(v2_farm, Farm::Orca { .. }));
+        }
+        let v2_farm = Farm::Orca {
+            name: Orca::ORCAUSDC,
+        };
+        let aqua_farm = AquaFarms::try_from(v2_farm).unwrap();
+        assert_eq!(aqua_farm, AquaFarms::ORCAUSDC);
+        assert_eq!(Farm::try_from(aqua_farm), Ok(v2_farm));
+        assert_eq!(
+            Farms::try_from(AquaFarms::USDCUSDT),
+            Err(ConversionError::NoLeveragedAquaFarm(AquaFarms::USDCUSDT))
+        );
+        assert_eq!(
+            Farm::try_from(Farms::GmtUsdcOrcaVault),
+            Err(ConversionError::NoV2Farm(Farms::GmtUsdcOrcaVault))
+        );
+
+        let table = farm_metadata_table();
+        let metadata = table
+            .iter()
+            .find(|metadata| metadata.farm == Farms::OrcaUsdcVault)
+            .unwrap();
+        assert_eq!(metadata.protocol, Protocol::Orca);
+        assert_eq!(metadata.aqua_farm, Some(AquaFarms::ORCAUSDC));
+        assert_eq!(metadata.v2_farm, Some(v2_farm));
+        assert_eq!(metadata.lp_mint, AquaFarms::ORCAUSDC.swap_pool_mint());
+        // every orca farm is described, and agrees with its configuration when one exists
+        assert_eq!(
+            table
+                .iter()
+                .filter(|metadata| metadata.protocol == Protocol::Orca)
+                .count(),
+            (0_u64..)
+                .map_while(|farm| Farms::try_from(farm).ok())
+                .filter(Farms::is_aquafarm)
+                .count()
+        );
+        for metadata in table.iter() {
+            if let Ok(config) = lev_farm_config(metadata.farm) {
+                assert_eq!(metadata.base_token_mint, config.base_token_mint);
+                assert_eq!(metadata.quote_token_mint, config.quote_token_mint);
+                assert_eq!(metadata.lp_mint, config.lp_mint);
+            }
+        }
+        assert!(table.iter().all(|metadata| metadata.farm.protocol()
+            == if metadata.aqua_farm.is_some() {
+                Protocol::Orca
+            } else {
+                Protocol::Raydium
+            }));
+    }
+}
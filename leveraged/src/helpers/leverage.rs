@@ -0,0 +1,276 @@
+//! calculates the amounts borrowed by `deposit_borrow_dual` for a target leverage.
+//!
+//! the position is opened with equal values of the coin and pc tokens, so each token is
+//! borrowed up to half of the position value, with any shortfall of one reserve's liquidity
+//! borrowed from the other reserve and swapped into place by the open flow.
+
+use super::error::PlannerError;
+use anchor_lang::prelude::ProgramError;
+use tulipv2_sdk_common::lending::reserve::Reserve;
+use tulipv2_sdk_common::math::{
+    common::{TryAdd, TryDiv, TryMul, TrySub},
+    decimal::Decimal,
+};
+
+/// a lending reserve which the leveraged farm borrows from
+#[derive(Clone, Copy, Debug)]
+pub struct LeverageReserve<'a> {
+    pub reserve: &'a Reserve,
+    /// pyth price of the reserve's liquidity token, see `load_validated_pyth_price`
+    pub price: Decimal,
+    /// maximum amount which may be borrowed from the reserve, if lower than its available liquidity
+    pub borrow_limit: Option<u64>,
+}
+
+/// amounts to borrow for a target leverage, and the resulting health of the position
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LeverageQuote {
+    pub coin_borrow_amount: u64,
+    pub pc_borrow_amount: u64,
+    /// value of the deposited coin and pc amounts
+    pub deposit_value: Decimal,
+    /// value of the coin and pc amounts borrowed
+    pub borrow_value: Decimal,
+    /// value of the position, which is split evenly between coin and pc after the swap
+    pub position_value: Decimal,
+    /// ratio of the borrowed value to the position value
+    pub loan_to_value: Decimal,
+    /// lowest loan to value ratio of the coin and pc reserves
+    pub max_loan_to_value: Decimal,
+    /// lowest liquidation threshold of the coin and pc reserves
+    pub liquidation_threshold: Decimal,
+    /// fraction by which the position value may fall, with its borrowed value unchanged,
+    /// before the position can be liquidated
+    pub liquidation_distance: Decimal,
+}
+
+/// calculates the coin and pc amounts to borrow so that depositing `coin_amount` and
+/// `pc_amount` results in a position worth `leverage` times the deposited value
+pub fn calculate_leverage(
+    coin_amount: u64,
+    pc_amount: u64,
+    leverage: Decimal,
+    coin: &LeverageReserve,
+    pc: &LeverageReserve,
+) -> std::result::Result<LeverageQuote, PlannerError> {
+    if leverage < Decimal::one() {
+        return Err(PlannerError::InvalidLeverage);
+    }
+    let coin_value = coin.value(coin_amount)?;
+    let pc_value = pc.value(pc_amount)?;
+    let deposit_value = coin_value.try_add(pc_value).map_err(math_error)?;
+    let position_value = deposit_value.try_mul(leverage).map_err(math_error)?;
+    let borrow_value = position_value.try_sub(deposit_value).map_err(math_error)?;
+    let loan_to_value = if position_value == Decimal::zero() {
+        Decimal::zero()
+    } else {
+        borrow_value.try_div(position_value).map_err(math_error)?
+    };
+    let max_loan_to_value = Decimal::from_percent(
+        coin.reserve
+            .config
+            .loan_to_value_ratio
+            .min(pc.reserve.config.loan_to_value_ratio),
+    );
+    if loan_to_value > max_loan_to_value {
+        return Err(PlannerError::LeverageTooHigh);
+    }
+
+    // borrow each token up to half of the position value, then move whatever a
+    // reserve can't supply over to the other reserve
+    let half_position_value = position_value.try_div(2).map_err(math_error)?;
+    let mut coin_borrow_value = if coin_value < half_position_value {
+        half_position_value
+            .try_sub(coin_value)
+            .map_err(math_error)?
+    } else {
+        Decimal::zero()
+    }
+    .min(borrow_value);
+    let mut pc_borrow_value = borrow_value
+        .try_sub(coin_borrow_value)
+        .map_err(math_error)?;
+    let coin_capacity = coin.value(coin.capacity())?;
+    let pc_capacity = pc.value(pc.capacity())?;
+    if coin_borrow_value > coin_capacity {
+        let shortfall = coin_borrow_value
+            .try_sub(coin_capacity)
+            .map_err(math_error)?;
+        pc_borrow_value = pc_borrow_value.try_add(shortfall).map_err(math_error)?;
+        coin_borrow_value = coin_capacity;
+    } else if pc_borrow_value > pc_capacity {
+        let shortfall = pc_borrow_value.try_sub(pc_capacity).map_err(math_error)?;
+        coin_borrow_value = coin_borrow_value.try_add(shortfall).map_err(math_error)?;
+        pc_borrow_value = pc_capacity;
+    }
+    if coin_borrow_value > coin_capacity {
+        return Err(PlannerError::InsufficientLiquidity(
+            coin.reserve.liquidity.mint_pubkey,
+        ));
+    }
+    if pc_borrow_value > pc_capacity {
+        return Err(PlannerError::InsufficientLiquidity(
+            pc.reserve.liquidity.mint_pubkey,
+        ));
+    }
+
+    let liquidation_threshold = Decimal::from_percent(
+        coin.reserve
+            .config
+            .liquidation_threshold
+            .min(pc.reserve.config.liquidation_threshold),
+    );
+    let liquidation_distance = if loan_to_value >= liquidation_threshold {
+        Decimal::zero()
+    } else {
+        Decimal::one()
+            .try_sub(
+                loan_to_value
+                    .try_div(liquidation_threshold)
+                    .map_err(math_error)?,
+            )
+            .map_err(math_error)?
+    };
+    Ok(LeverageQuote {
+        coin_borrow_amount: coin.amount(coin_borrow_value)?,
+        pc_borrow_amount: pc.amount(pc_borrow_value)?,
+        deposit_value,
+        borrow_value,
+        position_value,
+        loan_to_value,
+        max_loan_to_value,
+        liquidation_threshold,
+        liquidation_distance,
+    })
+}
+
+impl<'a> LeverageReserve<'a> {
+    /// the amount of liquidity which can be borrowed from the reserve
+    pub fn capacity(&self) -> u64 {
+        let available_amount = self.reserve.liquidity.available_amount;
+        self.borrow_limit
+            .map_or(available_amount, |limit| limit.min(available_amount))
+    }
+
+    /// returns the value of `amount` of the reserve's liquidity token
+    pub fn value(&self, amount: u64) -> std::result::Result<Decimal, PlannerError> {
+        Decimal::from(amount)
+            .try_mul(self.price)
+            .and_then(|value| value.try_div(self.decimals()?))
+            .map_err(math_error)
+    }
+
+    /// returns the amount of the reserve's liquidity token worth `value`, rounded down
+    pub fn amount(&self, value: Decimal) -> std::result::Result<u64, PlannerError> {
+        if value == Decimal::zero() {
+            return Ok(0);
+        }
+        value
+            .try_mul(self.decimals().map_err(math_error)?)
+            .and_then(|amount| amount.try_div(self.price))
+            .and_then(|amount| amount.try_floor_u64())
+            .map_err(math_error)
+    }
+
+    fn decimals(&self) -> std::result::Result<u64, ProgramError> {
+        10_u64
+            .checked_pow(self.reserve.liquidity.mint_decimals as u32)
+            .ok_or(ProgramError::InvalidArgument)
+    }
+}
+
+fn math_error<E>(_: E) -> PlannerError {
+    PlannerError::MathOverflow
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use anchor_lang::prelude::Pubkey;
+    use tulipv2_sdk_common::lending::reserve::{ReserveConfig, ReserveLiquidity};
+
+    fn mock_reserve(mint_decimals: u8, available_amount: u64) -> Reserve {
+        Reserve {
+            liquidity: ReserveLiquidity {
+                mint_pubkey: Pubkey::new_unique(),
+                mint_decimals,
+                available_amount,
+                ..Default::default()
+            },
+            config: ReserveConfig {
+                loan_to_value_ratio: 80,
+                liquidation_threshold: 90,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_calculate_leverage() {
+        // RAY at $2 with 6 decimals, USDC at $1 with 6 decimals
+        let (ray, usdc) = (
+            mock_reserve(6, 1_000_000_000),
+            mock_reserve(6, 1_000_000_000),
+        );
+        let coin = LeverageReserve {
+            reserve: &ray,
+            price: Decimal::from(2_u64),
+            borrow_limit: None,
+        };
+        let pc = LeverageReserve {
+            reserve: &usdc,
+            price: Decimal::one(),
+            borrow_limit: None,
+        };
+
+        // deposit $100 of USDC at 3x, so $150 of each token is held after the swap
+        let quote = calculate_leverage(0, 100_000_000, Decimal::from(3_u64), &coin, &pc).unwrap();
+        assert_eq!(quote.coin_borrow_amount, 75_000_000);
+        assert_eq!(quote.pc_borrow_amount, 50_000_000);
+        assert_eq!(quote.position_value, Decimal::from(300_u64));
+        assert_eq!(
+            quote.loan_to_value.try_mul(3).unwrap().try_round_u64(),
+            Ok(2)
+        );
+        // ltv of 2/3 with a 90% liquidation threshold
+        assert_eq!(
+            quote
+                .liquidation_distance
+                .try_mul(27)
+                .unwrap()
+                .try_round_u64(),
+            Ok(7)
+        );
+
+        // with only 50 RAY available the rest of the borrow is taken from USDC
+        let coin = LeverageReserve {
+            borrow_limit: Some(50_000_000),
+            ..coin
+        };
+        let quote = calculate_leverage(0, 100_000_000, Decimal::from(3_u64), &coin, &pc).unwrap();
+        assert_eq!(quote.coin_borrow_amount, 50_000_000);
+        assert_eq!(quote.pc_borrow_amount, 100_000_000);
+
+        let small_usdc = mock_reserve(6, 10_000_000);
+        let pc = LeverageReserve {
+            reserve: &small_usdc,
+            ..pc
+        };
+        assert_eq!(
+            calculate_leverage(0, 100_000_000, Decimal::from(3_u64), &coin, &pc),
+            Err(PlannerError::InsufficientLiquidity(
+                small_usdc.liquidity.mint_pubkey
+            ))
+        );
+        // 6x leverage has a loan to value of 5/6, above the 80% maximum
+        assert_eq!(
+            calculate_leverage(0, 100, Decimal::from(6_u64), &coin, &pc),
+            Err(PlannerError::LeverageTooHigh)
+        );
+        assert_eq!(
+            calculate_leverage(0, 100, Decimal::from_percent(50), &coin, &pc),
+            Err(PlannerError::InvalidLeverage)
+        );
+    }
+}
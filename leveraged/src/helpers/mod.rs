@@ -7,14 +7,28 @@ use crate::{
         Farms,
     },
     instructions::{
-        add_liquidity_stats, deposit_borrow_dual, deposit_raydium_vault, orca_add_liquidity_queue,
-        swap_tokens_raydium_stats, withdraw_orca_vault, withdraw_raydium_vault_close,
+        add_liquidity_stats, close_position_info, deposit_borrow_dual, deposit_raydium_vault,
+        orca_add_liquidity_queue, swap_tokens_orca_stats, swap_tokens_raydium_stats,
+        withdraw_orca_vault, withdraw_orca_vault_dd_close, withdraw_raydium_vault_close,
     },
 };
 
+pub mod close_position;
+pub mod error;
+pub mod farm_accounts;
+pub mod farm_metadata;
+pub mod leverage;
+pub mod open_position;
+pub mod position_accounts;
+pub mod position_report;
+pub mod resume_position;
+pub mod swap_quote;
+pub mod user_farms;
+
 use super::*;
 use crate::instructions::{create_user_farm, create_user_farm_obligation};
 use anchor_lang::prelude::*;
+use error::LevFarmConfigError;
 use solana_program::{instruction::Instruction, system_program, sysvar};
 use tulipv2_sdk_common::{
     config::levfarm::{LevFarmConfig, LENDING_PROGRAM},
@@ -24,9 +38,20 @@ use tulipv2_sdk_common::{
 /// returns an instruction that can be used to create a user farm account, and initialize
 /// the first obligation/position
 pub fn new_create_user_farm_ix(authority: Pubkey, farm: Farms) -> Option<Instruction> {
+    new_create_user_farm_at_index_ix(authority, farm, 0)
+}
+
+/// returns an instruction that can be used to create the user farm account with the given
+/// index, and initialize its first obligation/position. user farms are created in order, so
+/// `user_farm_index` must be the number of user farms the authority has for the farm
+pub fn new_create_user_farm_at_index_ix(
+    authority: Pubkey,
+    farm: Farms,
+    user_farm_index: u64,
+) -> Option<Instruction> {
     msg!("farm {:#?}", farm);
-    let levfarm_config = lev_farm_config(farm)?;
-    let user_farm_address = derive_user_farm_address(authority, crate::ID, 0, farm).0;
+    let levfarm_config = lev_farm_config(farm).ok()?;
+    let user_farm_address = derive_user_farm_address(authority, crate::ID, user_farm_index, farm).0;
     let obligation_vault_address =
         derive_user_farm_obligation_vault_address(user_farm_address, crate::ID, 0).0;
     let user_farm_obligation =
@@ -58,7 +83,7 @@ pub fn new_create_user_farm_obligation_ix(
     farm: Farms,
     obligation_index: u64,
 ) -> Option<Instruction> {
-    let levfarm_config = lev_farm_config(farm)?;
+    let levfarm_config = lev_farm_config(farm).ok()?;
     let obligation_vault_address = derive_user_farm_obligation_vault_address(
         user_farm_address,
         crate::ID,
@@ -128,6 +153,28 @@ pub fn new_swap_tokens_raydium_stats_ix(
         obligation_index,
     )
 }
+#[allow(clippy::too_many_arguments)]
+pub fn new_swap_tokens_orca_stats_ix(
+    accounts: Box<swap_tokens_orca_stats::OrcaSwap>,
+    serum_fee_recipient: Pubkey,
+    lending_market: Pubkey,
+    lending_market_authority: Pubkey,
+    lending_program: Pubkey,
+    lp_mint: Pubkey,
+    position_info_account: Pubkey,
+    obligation_index: u8,
+) -> Option<Instruction> {
+    swap_tokens_orca_stats::swap_tokens_orca_stats_keys(
+        accounts,
+        serum_fee_recipient,
+        lending_market,
+        lending_market_authority,
+        lending_program,
+        lp_mint,
+        position_info_account,
+        obligation_index,
+    )
+}
 pub fn new_add_liquidity_stats_ix(
     accounts: Box<add_liquidity_stats::AddLiquidity>,
     position_info_account: Pubkey,
@@ -148,7 +195,7 @@ pub fn new_deposit_raydium_vault_ix(
 ) -> Option<Instruction> {
     let (user_balance_account, balance_account_nonce) = Pubkey::find_program_address(
         &[
-            vault_info_account(farm).unwrap().as_ref(),
+            vault_info_account(farm).ok()?.as_ref(),
             accounts.obligation_vault_address.as_ref(),
         ],
         &accounts.vault_program,
@@ -188,7 +235,7 @@ pub fn new_withdraw_raydium_vault_ix(
 ) -> Option<Instruction> {
     let (user_balance_account, balance_account_nonce) = Pubkey::find_program_address(
         &[
-            vault_info_account(farm).unwrap().as_ref(),
+            vault_info_account(farm).ok()?.as_ref(),
             accounts.obligation_vault_address.as_ref(),
         ],
         &accounts.vault_program,
@@ -262,70 +309,205 @@ pub fn new_withdraw_orca_vault_without_shares_ix<'info>(
 ) -> Option<Instruction> {
     withdraw_orca_vault::withdraw_orca_vault_without_shares(accounts, obligation_index)
 }
-pub fn lev_farm_config(farm: Farms) -> Option<LevFarmConfig> {
+#[allow(clippy::too_many_arguments)]
+pub fn new_withdraw_orca_vault_dd_close_ix(
+    accounts: Box<withdraw_orca_vault_dd_close::WithdrawOrcaFarmDoubleDip>,
+    lending_market: Pubkey,
+    user_farm_obligation: Pubkey,
+    lending_market_authority: Pubkey,
+    lending_program: Pubkey,
+    position_info_account: Pubkey,
+    obligation_index: u8,
+    withdraw_percent: u8,
+    close_method: u8,
+) -> Option<Instruction> {
+    withdraw_orca_vault_dd_close::withdraw_orca_vault_dd_close_keys(
+        accounts,
+        lending_market,
+        user_farm_obligation,
+        lending_market_authority,
+        lending_program,
+        position_info_account,
+        obligation_index,
+        withdraw_percent,
+        close_method,
+    )
+}
+pub fn new_close_position_info_account_ix(
+    authority: Pubkey,
+    position_info_account: Pubkey,
+) -> Option<Instruction> {
+    close_position_info::close_position_info_account_keys(authority, position_info_account)
+}
+/// returns the configuration of the given leveraged farm.
+///
+/// farms whose config module does not yet contain their own addresses return
+/// `LevFarmConfigError::MissingConfig`
+pub fn lev_farm_config(farm: Farms) -> std::result::Result<LevFarmConfig, LevFarmConfigError> {
     match farm {
         Farms::RayUsdcVault => {
             #[cfg(not(feature = "ray-rayusdc-levfarm"))]
-            unimplemented!("requires rayusdc-levfarm feature to be activated");
+            return Err(LevFarmConfigError::FeatureDisabled(
+                farm,
+                "ray-rayusdc-levfarm",
+            ));
             #[cfg(feature = "ray-rayusdc-levfarm")]
-            return Some(tulipv2_sdk_common::config::levfarm::ray_rayusdc::get_lev_farm_config());
+            return Ok(tulipv2_sdk_common::config::levfarm::ray_rayusdc::get_lev_farm_config());
         }
         Farms::RaySrmVault => {
             #[cfg(not(feature = "ray-raysrm-levfarm"))]
-            unimplemented!("requires raysrm-levfarm feature to be activated");
+            return Err(LevFarmConfigError::FeatureDisabled(
+                farm,
+                "ray-raysrm-levfarm",
+            ));
             #[cfg(feature = "ray-raysrm-levfarm")]
-            return Some(tulipv2_sdk_common::config::levfarm::ray_rayusdc::get_lev_farm_config());
+            return Ok(tulipv2_sdk_common::config::levfarm::ray_raysrm::get_lev_farm_config());
         }
         Farms::RayUsdtVault => {
             #[cfg(not(feature = "ray-rayusdt-levfarm"))]
-            unimplemented!("requires rayusdt-levfarm feature to be activated");
+            return Err(LevFarmConfigError::FeatureDisabled(
+                farm,
+                "ray-rayusdt-levfarm",
+            ));
             #[cfg(feature = "ray-rayusdt-levfarm")]
-            return Some(tulipv2_sdk_common::config::levfarm::ray_rayusdt::get_lev_farm_config());
+            return Ok(tulipv2_sdk_common::config::levfarm::ray_rayusdt::get_lev_farm_config());
+        }
+        Farms::SolUsdcRayVault => {
+            #[cfg(not(feature = "ray-solusdc-levfarm"))]
+            return Err(LevFarmConfigError::FeatureDisabled(
+                farm,
+                "ray-solusdc-levfarm",
+            ));
+            #[cfg(feature = "ray-solusdc-levfarm")]
+            return Ok(tulipv2_sdk_common::config::levfarm::ray_solusdc::get_lev_farm_config());
         }
         Farms::OrcaUsdcVault => {
             #[cfg(not(feature = "orca-orcausdc-levfarm"))]
-            unimplemented!("requires orcausdc-levfarm feature to be activated");
+            return Err(LevFarmConfigError::FeatureDisabled(
+                farm,
+                "orca-orcausdc-levfarm",
+            ));
             #[cfg(feature = "orca-orcausdc-levfarm")]
-            return Some(tulipv2_sdk_common::config::levfarm::orca_orcausdc::get_lev_farm_config());
+            return Ok(tulipv2_sdk_common::config::levfarm::orca_orcausdc::get_lev_farm_config());
         }
-        _ => None,
+        // the remaining raydium config modules are copies of the RAY-USDC addresses,
+        // and the remaining orca config modules are empty
+        _ => Err(LevFarmConfigError::MissingConfig(farm)),
     }
 }
 
-pub fn vault_info_account(farm: Farms) -> Option<Pubkey> {
+/// returns the vault info account of the raydium vault used by the given leveraged farm,
+/// preferring the old vault info account when one is set
+pub fn vault_info_account(farm: Farms) -> std::result::Result<Pubkey, LevFarmConfigError> {
+    if farm.is_aquafarm() {
+        return Err(LevFarmConfigError::NoVaultInfoAccount(farm));
+    }
     match farm {
         Farms::RayUsdcVault => {
             #[cfg(not(feature = "ray-rayusdc-levfarm"))]
-            unimplemented!("requires rayusdc-levfarm feature to be activated");
+            return Err(LevFarmConfigError::FeatureDisabled(
+                farm,
+                "ray-rayusdc-levfarm",
+            ));
             #[cfg(feature = "ray-rayusdc-levfarm")]
-            if tulipv2_sdk_common::config::levfarm::ray_rayusdc::vault_config::OLD_VAULT_INFO_ACCOUNT.eq(&DEFAULT_KEY) {
-                Some(tulipv2_sdk_common::config::levfarm::ray_rayusdc::vault_config::VAULT_INFO_ACCOUNT)
-            } else {
-                Some(tulipv2_sdk_common::config::levfarm::ray_rayusdc::vault_config::OLD_VAULT_INFO_ACCOUNT)
+            {
+                use tulipv2_sdk_common::config::levfarm::ray_rayusdc::vault_config::*;
+                if OLD_VAULT_INFO_ACCOUNT.eq(&DEFAULT_KEY) {
+                    Ok(VAULT_INFO_ACCOUNT)
+                } else {
+                    Ok(OLD_VAULT_INFO_ACCOUNT)
+                }
             }
         }
         Farms::RaySrmVault => {
             #[cfg(not(feature = "ray-raysrm-levfarm"))]
-            unimplemented!("requires raysrm-levfarm feature to be activated");
+            return Err(LevFarmConfigError::FeatureDisabled(
+                farm,
+                "ray-raysrm-levfarm",
+            ));
             #[cfg(feature = "ray-raysrm-levfarm")]
-            if tulipv2_sdk_common::config::levfarm::ray_raysrm::vault_config::OLD_VAULT_INFO_ACCOUNT
-                .eq(&DEFAULT_KEY)
             {
-                Some(tulipv2_sdk_common::config::levfarm::ray_raysrm::vault_config::VAULT_INFO_ACCOUNT)
-            } else {
-                Some(tulipv2_sdk_common::config::levfarm::ray_raysrm::vault_config::OLD_VAULT_INFO_ACCOUNT)
+                use tulipv2_sdk_common::config::levfarm::ray_raysrm::vault_config::*;
+                if OLD_VAULT_INFO_ACCOUNT.eq(&DEFAULT_KEY) {
+                    Ok(VAULT_INFO_ACCOUNT)
+                } else {
+                    Ok(OLD_VAULT_INFO_ACCOUNT)
+                }
             }
         }
         Farms::RayUsdtVault => {
             #[cfg(not(feature = "ray-rayusdt-levfarm"))]
-            unimplemented!("requires rayusdt-levfarm feature to be activated");
+            return Err(LevFarmConfigError::FeatureDisabled(
+                farm,
+                "ray-rayusdt-levfarm",
+            ));
             #[cfg(feature = "ray-rayusdt-levfarm")]
-            if tulipv2_sdk_common::config::levfarm::ray_rayusdt::vault_config::OLD_VAULT_INFO_ACCOUNT.eq(&DEFAULT_KEY) {
-                Some(tulipv2_sdk_common::config::levfarm::ray_rayusdt::vault_config::VAULT_INFO_ACCOUNT)
-            } else {
-                Some(tulipv2_sdk_common::config::levfarm::ray_rayusdt::vault_config::OLD_VAULT_INFO_ACCOUNT)
+            {
+                use tulipv2_sdk_common::config::levfarm::ray_rayusdt::vault_config::*;
+                if OLD_VAULT_INFO_ACCOUNT.eq(&DEFAULT_KEY) {
+                    Ok(VAULT_INFO_ACCOUNT)
+                } else {
+                    Ok(OLD_VAULT_INFO_ACCOUNT)
+                }
+            }
+        }
+        Farms::SolUsdcRayVault => {
+            #[cfg(not(feature = "ray-solusdc-levfarm"))]
+            return Err(LevFarmConfigError::FeatureDisabled(
+                farm,
+                "ray-solusdc-levfarm",
+            ));
+            #[cfg(feature = "ray-solusdc-levfarm")]
+            return Ok(
+                tulipv2_sdk_common::config::levfarm::ray_solusdc::vault_config::VAULT_INFO_ACCOUNT,
+            );
+        }
+        _ => Err(LevFarmConfigError::MissingConfig(farm)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::accounts::derivations::derive_leveraged_farm_address;
+
+    #[test]
+    fn test_lev_farm_config() {
+        let mut configured = 0;
+        for farm in (0..=u8::MAX as u64).filter_map(|farm| Farms::try_from(farm).ok()) {
+            let config = match lev_farm_config(farm) {
+                Ok(config) => config,
+                Err(err) => {
+                    assert_eq!(err, LevFarmConfigError::MissingConfig(farm));
+                    continue;
+                }
+            };
+            configured += 1;
+            // the leveraged farm account is derived from the farm, so a config
+            // belonging to a different farm will not match
+            let (account, _) = derive_leveraged_farm_address(
+                config.solfarm_vault_program,
+                config.serum_market,
+                crate::ID,
+                farm,
+                false,
+            );
+            assert_eq!(account, config.account, "{:?}", farm);
+            match vault_info_account(farm) {
+                Ok(vault_info) => {
+                    assert!(!farm.is_aquafarm());
+                    assert_ne!(vault_info, DEFAULT_KEY);
+                }
+                Err(err) => {
+                    assert!(farm.is_aquafarm());
+                    assert_eq!(err, LevFarmConfigError::NoVaultInfoAccount(farm));
+                }
             }
         }
-        _ => None,
+        assert_eq!(configured, 5);
+        assert_ne!(
+            lev_farm_config(Farms::RaySrmVault).unwrap().account,
+            lev_farm_config(Farms::RayUsdcVault).unwrap().account
+        );
     }
 }
@@ -0,0 +1,464 @@
+//! plans the instructions needed to open a leveraged farm position.
+//!
+//! raydium farms are opened with `deposit_borrow_dual`, `swap_tokens_raydium_stats`,
+//! `add_liquidity_stats` and `deposit_raydium_vault`, while orca farms are opened with
+//! `deposit_borrow_dual`, `swap_tokens_orca_stats` and `orca_add_liquidity_queue`.
+//! the orca swap accounts aren't known to the sdk, so opening an orca position
+//! currently fails with `PlannerError::UnknownSwapAccounts`.
+//! the borrowed amounts are those of a `LeverageQuote` from `calculate_leverage`.
+//!
+//! the returned instructions reference more accounts than fit into a single
+//! transaction, and are expected to be sent one or two at a time, in order.
+
+use super::error::PlannerError;
+use super::farm_accounts::{RaydiumVaultAccounts, VaultAccounts};
+use super::leverage::LeverageQuote;
+use super::position_accounts::PositionAccounts;
+use super::*;
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account_idempotent,
+};
+use tulipv2_sdk_common::config::{
+    levfarm::BORROW_AUTHORIZER, ORCA_SWAP_PROGRAM, RAYDIUM_LIQUIDITY_V4, SERUM_DEX_V3,
+};
+
+/// maximum number of obligations managed by a single user farm
+pub const MAX_OBLIGATIONS: u8 = 3;
+
+/// returns the instructions needed to open the first position of a new user farm with the
+/// given index, depositing `coin_amount` and `pc_amount` from the authority's associated
+/// token accounts and borrowing the amounts of `quote`.
+///
+/// `quote` is expected to be calculated by `calculate_leverage` for the same deposit amounts
+pub fn new_open_position_ixs(
+    authority: Pubkey,
+    farm: Farms,
+    user_farm_index: u64,
+    coin_amount: u64,
+    pc_amount: u64,
+    quote: &LeverageQuote,
+) -> std::result::Result<Vec<Instruction>, PlannerError> {
+    OpenPositionPlanner::new(authority, farm, user_farm_index, 0)?.instructions(
+        true,
+        coin_amount,
+        pc_amount,
+        quote,
+    )
+}
+
+/// plans the instructions needed to open a position using a single obligation of a user farm
+pub struct OpenPositionPlanner {
+    pub position: PositionAccounts,
+}
+
+impl OpenPositionPlanner {
+    pub fn new(
+        authority: Pubkey,
+        farm: Farms,
+        user_farm_index: u64,
+        obligation_index: u8,
+    ) -> std::result::Result<Self, PlannerError> {
+        Ok(Self {
+            position: PositionAccounts::new(authority, farm, user_farm_index, obligation_index)?,
+        })
+    }
+
+    /// returns the ordered instructions used to open the position. when `create_obligation`
+    /// is true the user farm, or the additional obligation, is created first
+    pub fn instructions(
+        &self,
+        create_obligation: bool,
+        coin_amount: u64,
+        pc_amount: u64,
+        quote: &LeverageQuote,
+    ) -> std::result::Result<Vec<Instruction>, PlannerError> {
+        let mut instructions = Vec::with_capacity(6);
+        if create_obligation {
+            instructions.push(self.create_obligation_ix()?);
+        }
+        if let VaultAccounts::Raydium(_) = self.position.accounts.vault {
+            instructions.push(self.create_obligation_vault_lp_account_ix());
+        }
+        instructions.push(self.deposit_borrow_dual_ix(coin_amount, pc_amount, quote)?);
+        match self.position.accounts.vault {
+            VaultAccounts::Raydium(vault) => {
+                instructions.push(self.swap_tokens_raydium_ix()?);
+                instructions.push(self.add_liquidity_ix()?);
+                instructions.push(self.deposit_raydium_vault_ix(&vault)?);
+            }
+            VaultAccounts::Orca(_) => {
+                instructions.push(self.swap_tokens_orca_ix()?);
+                instructions.push(self.orca_add_liquidity_queue_ix()?);
+            }
+        }
+        Ok(instructions)
+    }
+
+    /// creates the user farm when opening the first obligation, otherwise creates
+    /// an additional obligation within the existing user farm
+    pub fn create_obligation_ix(&self) -> std::result::Result<Instruction, PlannerError> {
+        if self.position.obligation_index == 0 {
+            new_create_user_farm_at_index_ix(
+                self.position.authority,
+                self.position.farm,
+                self.position.user_farm_index,
+            )
+            .ok_or(PlannerError::InvalidInstruction("create_user_farm"))
+        } else {
+            new_create_user_farm_obligation_ix(
+                self.position.authority,
+                self.position.user_farm,
+                self.position.farm,
+                self.position.obligation_index as u64,
+            )
+            .ok_or(PlannerError::InvalidInstruction(
+                "create_user_farm_obligation",
+            ))
+        }
+    }
+
+    /// creates the lp token account of the obligation vault if it doesn't exist
+    pub fn create_obligation_vault_lp_account_ix(&self) -> Instruction {
+        create_associated_token_account_idempotent(
+            &self.position.authority,
+            &self.position.obligation_vault,
+            &self.position.config.lp_mint,
+            &spl_token::id(),
+        )
+    }
+
+    /// deposits the coin and pc amounts, and borrows the amounts of the quote
+    pub fn deposit_borrow_dual_ix(
+        &self,
+        coin_amount: u64,
+        pc_amount: u64,
+        quote: &LeverageQuote,
+    ) -> std::result::Result<Instruction, PlannerError> {
+        new_deposit_borrow_dual_ix(
+            deposit_borrow_dual::DepositBorrowDual {
+                authority: self.position.authority,
+                user_farm: self.position.user_farm,
+                leveraged_farm: self.position.config.account,
+                user_farm_obligation: self.position.user_farm_obligation,
+                coin_source_token_account: get_associated_token_address(
+                    &self.position.authority,
+                    &self.position.config.base_token_mint,
+                ),
+                coin_destination_token_account: self.position.config.base_token_account,
+                pc_source_token_account: get_associated_token_address(
+                    &self.position.authority,
+                    &self.position.config.quote_token_mint,
+                ),
+                pc_destination_token_account: self.position.config.quote_token_account,
+                coin_deposit_reserve_account: self.position.config.base_reserve,
+                pc_deposit_reserve_account: self.position.config.quote_reserve,
+                coin_reserve_liquidity_oracle: self.position.config.coin_price_account,
+                pc_reserve_liquidity_oracle: self.position.config.pc_price_account,
+                lending_market_account: self.position.config.lending_market,
+                derived_lending_market_authority: self.position.config.lending_market_authority,
+                token_program: spl_token::id(),
+                lending_program: self.position.config.lending_program,
+                coin_source_reserve_liquidity_token_account: self
+                    .position
+                    .accounts
+                    .base_reserve_liquidity_supply,
+                pc_source_reserve_liquidity_token_account: self
+                    .position
+                    .accounts
+                    .quote_reserve_liquidity_supply,
+                coin_reserve_liquidity_fee_receiver: self.position.config.coin_reserve_fee_receiver,
+                pc_reserve_liquidity_fee_receiver: self.position.config.pc_reserve_fee_receiver,
+                borrow_authorizer: BORROW_AUTHORIZER,
+                lp_pyth_price_account: self.position.config.lp_price_account,
+                vault_account: self.position.config.vault_account,
+                rent: sysvar::rent::id(),
+            },
+            self.position.position_info,
+            system_program::id(),
+            coin_amount,
+            pc_amount,
+            quote.coin_borrow_amount,
+            quote.pc_borrow_amount,
+            self.position.obligation_index,
+        )
+        .ok_or(PlannerError::InvalidInstruction("deposit_borrow_dual"))
+    }
+
+    /// swaps the leveraged farm's coin and pc balances to equal values through the raydium amm
+    pub fn swap_tokens_raydium_ix(&self) -> std::result::Result<Instruction, PlannerError> {
+        new_swap_tokens_raydium_stats_ix(
+            Box::new(swap_tokens_raydium_stats::RaydiumSwap {
+                authority: self.position.authority,
+                leveraged_farm: self.position.config.account,
+                user_farm: self.position.user_farm,
+                user_farm_obligation: self.position.user_farm_obligation,
+                token_program: spl_token::id(),
+                vault_signer: self.position.accounts.serum_vault_signer,
+                swap_or_liquidity_program_id: RAYDIUM_LIQUIDITY_V4,
+                amm_id: self.position.config.amm_id,
+                amm_authority: self.position.accounts.amm_authority,
+                amm_open_orders: self.position.config.amm_open_orders,
+                amm_quantities_or_target_orders: self
+                    .position
+                    .config
+                    .amm_quantities_or_target_orders,
+                pool_coin_tokenaccount: self.position.config.amm_coin_account,
+                pool_pc_tokenaccount: self.position.config.amm_pc_account,
+                serum_program_id: SERUM_DEX_V3,
+                serum_market: self.position.config.serum_market,
+                serum_bids: self.position.config.serum_bids,
+                serum_asks: self.position.config.serum_asks,
+                serum_event_queue: self.position.config.serum_event_queue,
+                serum_coin_vault_account: self.position.config.serum_coin_vault,
+                serum_pc_vault_account: self.position.config.serum_pc_vault,
+                serum_vault_signer: self.position.accounts.serum_vault_signer,
+                coin_wallet: self.position.config.base_token_account,
+                pc_wallet: self.position.config.quote_token_account,
+            }),
+            self.position.config.lending_market,
+            self.position.config.lending_market_authority,
+            self.position.config.lending_program,
+            self.position.position_info,
+            self.position.obligation_index,
+        )
+        .ok_or(PlannerError::InvalidInstruction(
+            "swap_tokens_raydium_stats",
+        ))
+    }
+
+    /// swaps the leveraged farm's coin and pc balances to equal values through the orca
+    /// pool. the accounts of the orca pool aren't part of the leveraged farm configs, so
+    /// the swap can't be planned yet and an error is always returned
+    pub fn swap_tokens_orca_ix(&self) -> std::result::Result<Instruction, PlannerError> {
+        Err(PlannerError::UnknownSwapAccounts(self.position.farm))
+    }
+
+    /// adds the swapped balances as liquidity to the raydium amm
+    pub fn add_liquidity_ix(&self) -> std::result::Result<Instruction, PlannerError> {
+        new_add_liquidity_stats_ix(
+            Box::new(add_liquidity_stats::AddLiquidity {
+                authority: self.position.authority,
+                user_farm: self.position.user_farm,
+                leveraged_farm: self.position.config.account,
+                liquidity_program_id: RAYDIUM_LIQUIDITY_V4,
+                amm_id: self.position.config.amm_id,
+                amm_authority: self.position.accounts.amm_authority,
+                amm_open_orders: self.position.config.amm_open_orders,
+                amm_quantities_or_target_orders: self
+                    .position
+                    .config
+                    .amm_quantities_or_target_orders,
+                lp_mint_address: self.position.config.lp_mint,
+                pool_coin_token_account: self.position.config.amm_coin_account,
+                pool_pc_token_account: self.position.config.amm_pc_account,
+                serum_market: self.position.config.serum_market,
+                token_program: spl_token::id(),
+                lev_farm_coin_token_account: self.position.config.base_token_account,
+                lev_farm_pc_token_account: self.position.config.quote_token_account,
+                user_lp_token_account: self.position.obligation_vault_lp_account(),
+                pyth_price_account: self.position.config.lp_price_account,
+                lending_market_account: self.position.config.lending_market,
+                user_farm_obligation: self.position.user_farm_obligation,
+                derived_lending_market_authority: self.position.config.lending_market_authority,
+                lending_program: self.position.config.lending_program,
+                clock: sysvar::clock::id(),
+                dex_program: SERUM_DEX_V3,
+            }),
+            self.position.position_info,
+            self.position.obligation_index,
+        )
+        .ok_or(PlannerError::InvalidInstruction("add_liquidity_stats"))
+    }
+
+    /// deposits the lp tokens held by the obligation vault into the raydium vault
+    pub fn deposit_raydium_vault_ix(
+        &self,
+        vault: &RaydiumVaultAccounts,
+    ) -> std::result::Result<Instruction, PlannerError> {
+        let stake_pool = vault
+            .stake_pool
+            .ok_or(PlannerError::MissingStakePool(self.position.farm))?;
+        let vault_program = self.position.config.solfarm_vault_program;
+        let (user_balance_account, user_balance_metadata) =
+            vault.user_balance_accounts(&vault_program, &self.position.obligation_vault);
+        new_deposit_raydium_vault_ix(
+            Box::new(deposit_raydium_vault::DepositFarm {
+                authority: self.position.authority,
+                user_farm: self.position.user_farm,
+                obligation_vault_address: self.position.obligation_vault,
+                leveraged_farm: self.position.config.account,
+                vault_program,
+                authority_token_account: self.position.obligation_vault_lp_account(),
+                vault_pda_account: self.position.accounts.vault_pda,
+                vault: self.position.config.vault_account,
+                lp_token_account: vault.lp_token_account,
+                user_balance_account,
+                system_program: system_program::id(),
+                stake_program_id: stake_pool.stake_program,
+                pool_id: stake_pool.pool_id,
+                pool_authority: vault.pool_authority,
+                vault_info_account: vault.vault_info_account,
+                pool_lp_token_account: stake_pool.pool_lp_token_account,
+                user_reward_a_token_account: vault.reward_a_token_account,
+                pool_reward_a_token_account: vault.pool_reward_a_token_account,
+                user_reward_b_token_account: vault.reward_b_token_account,
+                pool_reward_b_token_account: vault.pool_reward_b_token_account,
+                clock: sysvar::clock::id(),
+                rent: sysvar::rent::id(),
+                token_program_id: spl_token::id(),
+                user_balance_metadata,
+            }),
+            self.position.config.lending_market,
+            self.position.user_farm_obligation,
+            self.position.config.lending_market_authority,
+            self.position.config.lending_program,
+            self.position.obligation_index as u64,
+            self.position.farm,
+        )
+        .ok_or(PlannerError::InvalidInstruction("deposit_raydium_vault"))
+    }
+
+    /// adds the leveraged farm's coin and pc balances as liquidity to the orca pool,
+    /// queueing the lp tokens for deposit into the orca vault
+    pub fn orca_add_liquidity_queue_ix(&self) -> std::result::Result<Instruction, PlannerError> {
+        let vault_deposit_queue = match self.position.accounts.vault {
+            VaultAccounts::Orca(vault) => vault.vault_deposit_queue,
+            VaultAccounts::Raydium(_) => {
+                return Err(PlannerError::InvalidInstruction("orca_add_liquidity_queue"))
+            }
+        };
+        new_orca_add_liquidity_queue_ix(
+            Box::new(orca_add_liquidity_queue::OrcaAddLiquidityQueue {
+                authority: self.position.authority,
+                user_farm: self.position.user_farm,
+                leveraged_farm: self.position.config.account,
+                vault_account: self.position.config.vault_account,
+                vault_user_account: self.position.orca_vault_user_account(),
+                token_program: spl_token::id(),
+                rent: sysvar::rent::id(),
+                vault_pda: self.position.accounts.vault_pda,
+                system_program: system_program::id(),
+                lev_farm_coin_token_account: self.position.config.base_token_account,
+                lev_farm_pc_token_account: self.position.config.quote_token_account,
+                pool_coin_token_account: self.position.config.amm_coin_account,
+                pool_pc_token_account: self.position.config.amm_pc_account,
+                liquidity_program_id: ORCA_SWAP_PROGRAM,
+                amm_id: self.position.config.amm_id,
+                amm_authority: self.position.accounts.amm_authority,
+                vault_deposit_queue,
+                lp_mint_address: self.position.config.lp_mint,
+                lending_market_account: self.position.config.lending_market,
+                user_farm_obligation: self.position.user_farm_obligation,
+                derived_lending_market_authority: self.position.config.lending_market_authority,
+                lending_program: self.position.config.lending_program,
+                dex_program: SERUM_DEX_V3,
+                solfarm_vault_program: self.position.config.solfarm_vault_program,
+                obligation_vault_address: self.position.obligation_vault,
+            }),
+            self.position.position_info,
+            self.position.obligation_index,
+        )
+        .ok_or(PlannerError::InvalidInstruction("orca_add_liquidity_queue"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::leverage::{calculate_leverage, LeverageReserve};
+    use super::*;
+    use tulipv2_sdk_common::lending::reserve::{Reserve, ReserveConfig, ReserveLiquidity};
+    use tulipv2_sdk_common::math::decimal::Decimal;
+
+    fn mock_reserve() -> Reserve {
+        Reserve {
+            liquidity: ReserveLiquidity {
+                mint_pubkey: Pubkey::new_unique(),
+                available_amount: 1_000,
+                ..Default::default()
+            },
+            config: ReserveConfig {
+                loan_to_value_ratio: 50,
+                liquidation_threshold: 60,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    /// quotes a 2x position of 100 coin and 200 pc, with both tokens priced at 1
+    fn quote() -> LeverageQuote {
+        let (coin, pc) = (mock_reserve(), mock_reserve());
+        let reserve = |reserve| LeverageReserve {
+            reserve,
+            price: Decimal::one(),
+            borrow_limit: None,
+        };
+        calculate_leverage(
+            100,
+            200,
+            Decimal::from(2_u64),
+            &reserve(&coin),
+            &reserve(&pc),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_open_raydium_position() {
+        let authority = Pubkey::new_unique();
+        let ixs =
+            new_open_position_ixs(authority, Farms::RayUsdcVault, 0, 100, 200, &quote()).unwrap();
+        assert_eq!(ixs.len(), 6);
+        assert_eq!(ixs[1].program_id, spl_associated_token_account::id());
+        for ix in ixs.iter().filter(|ix| ix.program_id == crate::ID) {
+            assert_eq!(ix.accounts[0].pubkey, authority);
+            assert!(ix.accounts[0].is_signer);
+        }
+        // the borrowed amounts are those of the quote, so both tokens are worth 300
+        assert_eq!(ixs[2].data[24..32], 200_u64.to_le_bytes());
+        assert_eq!(ixs[2].data[32..40], 100_u64.to_le_bytes());
+
+        let planner = OpenPositionPlanner::new(authority, Farms::RayUsdcVault, 0, 1).unwrap();
+        let ixs = planner.instructions(false, 100, 200, &quote()).unwrap();
+        assert_eq!(ixs.len(), 5);
+        // deposit_borrow_dual uses the position info account of the obligation
+        assert_eq!(
+            ixs[1].accounts[ixs[1].accounts.len() - 2].pubkey,
+            planner.position.position_info
+        );
+
+        // the first obligation of a second user farm creates that user farm
+        let planner = OpenPositionPlanner::new(authority, Farms::RayUsdcVault, 1, 0).unwrap();
+        assert_eq!(
+            planner.position.user_farm,
+            derive_user_farm_address(authority, crate::ID, 1, Farms::RayUsdcVault).0
+        );
+        let ixs = planner.instructions(true, 100, 200, &quote()).unwrap();
+        assert_eq!(ixs[0].accounts[1].pubkey, planner.position.user_farm);
+
+        // the stake pool of RAY-SRM is not configured
+        assert_eq!(
+            new_open_position_ixs(authority, Farms::RaySrmVault, 0, 100, 200, &quote()),
+            Err(PlannerError::MissingStakePool(Farms::RaySrmVault))
+        );
+        assert_eq!(
+            OpenPositionPlanner::new(authority, Farms::RayUsdcVault, 0, MAX_OBLIGATIONS).err(),
+            Some(PlannerError::InvalidObligationIndex(MAX_OBLIGATIONS))
+        );
+    }
+
+    #[test]
+    fn test_open_orca_position() {
+        let authority = Pubkey::new_unique();
+        assert_eq!(
+            new_open_position_ixs(authority, Farms::OrcaUsdcVault, 0, 100, 200, &quote()),
+            Err(PlannerError::UnknownSwapAccounts(Farms::OrcaUsdcVault))
+        );
+        assert_eq!(
+            new_open_position_ixs(authority, Farms::OrcaSolVault, 0, 100, 200, &quote()),
+            Err(PlannerError::Config(LevFarmConfigError::MissingConfig(
+                Farms::OrcaSolVault
+            )))
+        );
+    }
+}
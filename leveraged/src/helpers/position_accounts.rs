@@ -0,0 +1,84 @@
+//! accounts of a single obligation of a user farm, shared by the open and close planners
+
+use super::error::PlannerError;
+use super::farm_accounts::{farm_accounts, FarmAccounts};
+use super::open_position::MAX_OBLIGATIONS;
+use super::*;
+use crate::accounts::derivations::{
+    derive_orca_vault_user_address, derive_user_position_info_address,
+};
+use spl_associated_token_account::get_associated_token_address;
+
+/// the farm accounts, and the derived user farm accounts, of the obligation at
+/// `obligation_index` of the authority's user farm at `user_farm_index`
+pub struct PositionAccounts {
+    pub authority: Pubkey,
+    pub farm: Farms,
+    pub user_farm_index: u64,
+    pub obligation_index: u8,
+    pub config: LevFarmConfig,
+    pub accounts: FarmAccounts,
+    pub user_farm: Pubkey,
+    pub user_farm_obligation: Pubkey,
+    pub obligation_vault: Pubkey,
+    pub position_info: Pubkey,
+}
+
+impl PositionAccounts {
+    pub fn new(
+        authority: Pubkey,
+        farm: Farms,
+        user_farm_index: u64,
+        obligation_index: u8,
+    ) -> std::result::Result<Self, PlannerError> {
+        if obligation_index >= MAX_OBLIGATIONS {
+            return Err(PlannerError::InvalidObligationIndex(obligation_index));
+        }
+        let config = lev_farm_config(farm)?;
+        let accounts = farm_accounts(farm)?;
+        let user_farm = derive_user_farm_address(authority, crate::ID, user_farm_index, farm).0;
+        Ok(Self {
+            authority,
+            farm,
+            user_farm_index,
+            obligation_index,
+            config,
+            accounts,
+            user_farm,
+            user_farm_obligation: derive_user_farm_obligation_address(
+                authority,
+                user_farm,
+                crate::ID,
+                obligation_index,
+            )
+            .0,
+            obligation_vault: derive_user_farm_obligation_vault_address(
+                user_farm,
+                crate::ID,
+                obligation_index,
+            )
+            .0,
+            position_info: derive_user_position_info_address(
+                user_farm,
+                crate::ID,
+                obligation_index,
+            )
+            .0,
+        })
+    }
+
+    /// the lp token account owned by the obligation vault
+    pub fn obligation_vault_lp_account(&self) -> Pubkey {
+        get_associated_token_address(&self.obligation_vault, &self.config.lp_mint)
+    }
+
+    /// the authority's user account of the orca vault
+    pub fn orca_vault_user_account(&self) -> Pubkey {
+        derive_orca_vault_user_address(
+            &self.config.solfarm_vault_program,
+            &self.config.vault_account,
+            &self.authority,
+        )
+        .0
+    }
+}
@@ -0,0 +1,252 @@
+//! summarizes the cost basis and profit of a position from its position info account.
+//!
+//! the position info account records the coin and pc put into the position's lp tokens
+//! (`open_coin_cost`, `open_pc_cost`), the cost of the lp tokens withdrawn so far
+//! (`withdraw_coin_cost`, `withdraw_pc_cost`), the amounts those withdrawals returned
+//! (`withdraw_coin`, `withdraw_pc`) and the net amounts received from swaps (`coin_swap`,
+//! `pc_swap`, negative when tokens were sold). profit is measured against the cost of the
+//! lp tokens, so interest accrued on the obligation's borrows is not included.
+
+use crate::accounts::position_info::PositionInfo;
+use anchor_lang::prelude::ProgramError;
+use tulipv2_sdk_common::math::{
+    common::{TryAdd, TryDiv, TryMul, TrySub},
+    decimal::Decimal,
+};
+
+/// signed coin and pc amounts, in the tokens' base units
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TokenAmounts {
+    pub coin: i128,
+    pub pc: i128,
+}
+
+/// usd prices of the position's tokens, used to value `TokenAmounts`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PositionPrices {
+    pub coin_price: Decimal,
+    pub coin_decimals: u8,
+    pub pc_price: Decimal,
+    pub pc_decimals: u8,
+}
+
+/// a signed usd value, as `Decimal` can't be negative
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UsdValue {
+    pub value: Decimal,
+    /// whether the value is a loss. zero is never negative
+    pub is_negative: bool,
+}
+
+/// the lp tokens the position currently holds, and the coin and pc they can be redeemed for
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CurrentPosition {
+    pub lp_amount: u64,
+    pub coin_amount: u64,
+    pub pc_amount: u64,
+}
+
+/// cost basis and profit of a position, in coin and pc
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PositionReport {
+    /// coin and pc cost of the lp tokens which have not been withdrawn
+    pub cost_basis: TokenAmounts,
+    /// amounts returned by withdrawals, less the cost of the lp tokens withdrawn
+    pub realized_pnl: TokenAmounts,
+    /// amounts the current lp tokens can be redeemed for, less their cost
+    pub unrealized_pnl: TokenAmounts,
+    /// net amounts received from swaps
+    pub net_swap: TokenAmounts,
+    /// lp tokens held or withdrawn in excess of those deposited, such as compounded rewards
+    pub lp_growth: i128,
+    /// the position info account's `close_method` and `settle_method`, kept as raw values
+    /// as the leveraged farm program doesn't publish which method each value selects
+    pub close_method: u8,
+    pub settle_method: u8,
+}
+
+impl TokenAmounts {
+    /// returns the usd value of the amounts
+    pub fn usd_value(
+        &self,
+        prices: &PositionPrices,
+    ) -> std::result::Result<UsdValue, ProgramError> {
+        token_value(self.coin, prices.coin_price, prices.coin_decimals)?.try_add(token_value(
+            self.pc,
+            prices.pc_price,
+            prices.pc_decimals,
+        )?)
+    }
+}
+
+impl UsdValue {
+    pub fn new(value: Decimal, is_negative: bool) -> Self {
+        Self {
+            value,
+            is_negative: is_negative && value != Decimal::zero(),
+        }
+    }
+
+    /// returns the value with its sign flipped
+    pub fn negate(self) -> Self {
+        Self::new(self.value, !self.is_negative)
+    }
+}
+
+impl TryAdd for UsdValue {
+    fn try_add(self, rhs: Self) -> std::result::Result<Self, ProgramError> {
+        if self.is_negative == rhs.is_negative {
+            return Ok(Self::new(self.value.try_add(rhs.value)?, self.is_negative));
+        }
+        if self.value >= rhs.value {
+            Ok(Self::new(self.value.try_sub(rhs.value)?, self.is_negative))
+        } else {
+            Ok(Self::new(rhs.value.try_sub(self.value)?, rhs.is_negative))
+        }
+    }
+}
+
+impl PositionReport {
+    pub fn new(position_info: &PositionInfo, current: &CurrentPosition) -> Self {
+        let cost_basis = TokenAmounts {
+            coin: position_info.open_coin_cost as i128 - position_info.withdraw_coin_cost as i128,
+            pc: position_info.open_pc_cost as i128 - position_info.withdraw_pc_cost as i128,
+        };
+        Self {
+            cost_basis,
+            realized_pnl: TokenAmounts {
+                coin: position_info.withdraw_coin as i128
+                    - position_info.withdraw_coin_cost as i128,
+                pc: position_info.withdraw_pc as i128 - position_info.withdraw_pc_cost as i128,
+            },
+            unrealized_pnl: TokenAmounts {
+                coin: current.coin_amount as i128 - cost_basis.coin,
+                pc: current.pc_amount as i128 - cost_basis.pc,
+            },
+            net_swap: TokenAmounts {
+                coin: position_info.coin_swap as i128,
+                pc: position_info.pc_swap as i128,
+            },
+            lp_growth: current.lp_amount as i128 + position_info.withdraw_lp as i128
+                - position_info.deposit_lp as i128,
+            close_method: position_info.close_method,
+            settle_method: position_info.settle_method,
+        }
+    }
+
+    /// returns the realized and unrealized pnl
+    pub fn total_pnl(&self) -> TokenAmounts {
+        TokenAmounts {
+            coin: self.realized_pnl.coin + self.unrealized_pnl.coin,
+            pc: self.realized_pnl.pc + self.unrealized_pnl.pc,
+        }
+    }
+
+    /// returns the usd value lost to swaps, which is negative if swaps were profitable
+    pub fn net_swap_cost(
+        &self,
+        prices: &PositionPrices,
+    ) -> std::result::Result<UsdValue, ProgramError> {
+        Ok(self.net_swap.usd_value(prices)?.negate())
+    }
+}
+
+fn token_value(
+    amount: i128,
+    price: Decimal,
+    decimals: u8,
+) -> std::result::Result<UsdValue, ProgramError> {
+    let scale = 10_u64
+        .checked_pow(decimals as u32)
+        .ok_or(ProgramError::InvalidArgument)?;
+    let value = Decimal::from(amount.unsigned_abs())
+        .try_mul(price)?
+        .try_div(scale)?;
+    Ok(UsdValue::new(value, amount < 0))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bytemuck::Zeroable;
+
+    #[test]
+    fn test_position_report() {
+        let mut position_info = PositionInfo::zeroed();
+        position_info.open_coin_cost = 100_000_000;
+        position_info.open_pc_cost = 200_000_000;
+        position_info.withdraw_coin_cost = 50_000_000;
+        position_info.withdraw_pc_cost = 100_000_000;
+        position_info.withdraw_coin = 55_000_000;
+        position_info.withdraw_pc = 90_000_000;
+        // sold 1 coin for 1.9 pc
+        position_info.coin_swap = -1_000_000;
+        position_info.pc_swap = 1_900_000;
+        position_info.deposit_lp = 1_000;
+        position_info.withdraw_lp = 500;
+        position_info.close_method = 2;
+        position_info.settle_method = 7;
+
+        let report = PositionReport::new(
+            &position_info,
+            &CurrentPosition {
+                lp_amount: 520,
+                coin_amount: 48_000_000,
+                pc_amount: 104_000_000,
+            },
+        );
+        assert_eq!(
+            report.cost_basis,
+            TokenAmounts {
+                coin: 50_000_000,
+                pc: 100_000_000
+            }
+        );
+        assert_eq!(
+            report.realized_pnl,
+            TokenAmounts {
+                coin: 5_000_000,
+                pc: -10_000_000
+            }
+        );
+        assert_eq!(
+            report.total_pnl(),
+            TokenAmounts {
+                coin: 3_000_000,
+                pc: -6_000_000
+            }
+        );
+        assert_eq!(report.lp_growth, 20);
+        assert_eq!(report.close_method, 2);
+        assert_eq!(report.settle_method, 7);
+
+        // coin at $2 and pc at $1, both with 6 decimals
+        let prices = PositionPrices {
+            coin_price: Decimal::from(2_u64),
+            coin_decimals: 6,
+            pc_price: Decimal::one(),
+            pc_decimals: 6,
+        };
+        // the $6 of coin gained offsets the $6 of pc lost
+        assert_eq!(
+            report.total_pnl().usd_value(&prices),
+            Ok(UsdValue::new(Decimal::zero(), false))
+        );
+        assert_eq!(
+            report.realized_pnl.usd_value(&prices),
+            Ok(UsdValue::new(Decimal::zero(), false))
+        );
+        // selling $2 of coin for $1.90 of pc cost $0.10
+        assert_eq!(
+            report.net_swap_cost(&prices),
+            Ok(UsdValue::new(Decimal::from_percent(10), false))
+        );
+        assert_eq!(
+            TokenAmounts { coin: -1, pc: 0 }.usd_value(&prices),
+            Ok(UsdValue::new(
+                Decimal::from(2_u64).try_div(1_000_000).unwrap(),
+                true
+            ))
+        );
+    }
+}
@@ -0,0 +1,261 @@
+//! plans the instructions needed to finish an interrupted leveraged farm flow.
+//!
+//! opening, topping up and closing a position each take several transactions, and the
+//! obligation's `position_state` records how far the flow got. open and top up flows are
+//! resumed from any of their steps, while a close is only resumed after the double dip
+//! withdrawal of an orca farm, which is the only close step built by this crate.
+//!
+//! the rest of the close flow, from `Withdrawing` through `RemovedLiquidity`,
+//! `SwappedForRepaying`, `Repaid` and `Withdrawn`, is carried out by the program's
+//! `remove_liquidity_new`, `swap_tokens_to_repay_*`, `repay_obligation_liquidity` and
+//! `close_position` instructions. only their sighashes are known to this sdk, not their
+//! accounts, so resuming a close from `RemovedLiquidity` or any other of these states isn't
+//! supported yet, and returns `PlannerError::UnsupportedCloseStep` along with the states the
+//! flow moves the position to next. positions being liquidated, or which aren't part of an
+//! interrupted flow, return `PlannerError::CannotResume`.
+
+use super::close_position::{ClosePositionPlanner, FULL_CLOSE_PERCENT};
+use super::error::PlannerError;
+use super::farm_accounts::VaultAccounts;
+use super::open_position::{OpenPositionPlanner, MAX_OBLIGATIONS};
+use super::*;
+use crate::accounts::{position_info::PositionInfo, user_farm::UserFarm, Position};
+
+/// returns the remaining instructions needed to finish the flow which the obligation at
+/// `obligation_index` of `user_farm` was interrupted in.
+///
+/// unused obligations are stored zeroed, which decodes as `Opening`, so obligations which
+/// haven't been created, or are `Opening` without any deposited amounts, return
+/// `PlannerError::NoPosition`
+pub fn new_resume_position_ixs(
+    authority: Pubkey,
+    farm: Farms,
+    obligation_index: u8,
+    user_farm: &UserFarm,
+    position_info: &PositionInfo,
+) -> std::result::Result<Vec<Instruction>, PlannerError> {
+    let obligation = user_farm
+        .obligations
+        .get(obligation_index as usize)
+        .ok_or(PlannerError::InvalidObligationIndex(obligation_index))?;
+    let position_state = obligation.state()?;
+    let unused = obligation_index >= user_farm.number_of_obligations
+        || obligation.obligation_account == Pubkey::default()
+        || (position_state == Position::Opening
+            && obligation.coin_amount == 0
+            && obligation.pc_amount == 0);
+    if unused {
+        return Err(PlannerError::NoPosition(obligation_index));
+    }
+    resume_position_ixs(
+        authority,
+        farm,
+        user_farm.user_farm_number as u64,
+        obligation_index,
+        position_state,
+        position_info,
+    )
+}
+
+/// returns the remaining instructions needed to move the position held by the obligation at
+/// `obligation_index` of the user farm at `user_farm_index` out of `position_state`, and
+/// finish the open, top up or close flow it is part of.
+///
+/// a close interrupted after `withdraw_orca_vault_dd_close` uses the `withdraw_percent`
+/// and `close_method` recorded in the position info account
+pub fn resume_position_ixs(
+    authority: Pubkey,
+    farm: Farms,
+    user_farm_index: u64,
+    obligation_index: u8,
+    position_state: Position,
+    position_info: &PositionInfo,
+) -> std::result::Result<Vec<Instruction>, PlannerError> {
+    if obligation_index >= MAX_OBLIGATIONS {
+        return Err(PlannerError::InvalidObligationIndex(obligation_index));
+    }
+    let cannot_resume = PlannerError::CannotResume {
+        state: position_state,
+        next: position_state.next_states(),
+    };
+    match position_state {
+        Position::Opening
+        | Position::TopUp
+        | Position::Borrowed
+        | Position::Swapped
+        | Position::TopUpSwapped
+        | Position::AddedLiquidity
+        | Position::TopUpAddedLiquidity => {
+            let planner =
+                OpenPositionPlanner::new(authority, farm, user_farm_index, obligation_index)?;
+            match planner.position.accounts.vault {
+                VaultAccounts::Raydium(vault) => {
+                    let mut instructions = Vec::with_capacity(3);
+                    if matches!(
+                        position_state,
+                        Position::Opening | Position::TopUp | Position::Borrowed
+                    ) {
+                        instructions.push(planner.swap_tokens_raydium_ix()?);
+                    }
+                    if !matches!(
+                        position_state,
+                        Position::AddedLiquidity | Position::TopUpAddedLiquidity
+                    ) {
+                        instructions.push(planner.add_liquidity_ix()?);
+                    }
+                    instructions.push(planner.deposit_raydium_vault_ix(&vault)?);
+                    Ok(instructions)
+                }
+                // lp tokens added to the queue are deposited into the aquafarm by the vault
+                VaultAccounts::Orca(_) => match position_state {
+                    Position::AddedLiquidity | Position::TopUpAddedLiquidity => Err(cannot_resume),
+                    Position::Opening | Position::TopUp | Position::Borrowed => Ok(vec![
+                        planner.swap_tokens_orca_ix()?,
+                        planner.orca_add_liquidity_queue_ix()?,
+                    ]),
+                    _ => Ok(vec![planner.orca_add_liquidity_queue_ix()?]),
+                },
+            }
+        }
+        Position::WithdrawnOrcaDoubleDip => {
+            let planner =
+                ClosePositionPlanner::new(authority, farm, user_farm_index, obligation_index)?;
+            let vault = match planner.position.accounts.vault {
+                VaultAccounts::Orca(vault) => vault,
+                VaultAccounts::Raydium(_) => return Err(cannot_resume),
+            };
+            let withdraw_percent = u8::try_from(position_info.withdraw_percent)
+                .ok()
+                .filter(|percent| (1..=FULL_CLOSE_PERCENT).contains(percent))
+                .ok_or(PlannerError::InvalidWithdrawPercent(
+                    position_info.withdraw_percent.min(u8::MAX as u32) as u8,
+                ))?;
+            Ok(vec![planner.withdraw_orca_vault_ix(
+                &vault,
+                withdraw_percent,
+                position_info.close_method,
+            )?])
+        }
+        Position::Withdrawing
+        | Position::RemovedLiquidity
+        | Position::SwappedForRepaying
+        | Position::Repaid
+        | Position::Withdrawn => Err(PlannerError::UnsupportedCloseStep {
+            state: position_state,
+            next: position_state.next_states(),
+        }),
+        _ => Err(cannot_resume),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bytemuck::Zeroable;
+
+    #[test]
+    fn test_resume_raydium_position() {
+        let authority = Pubkey::new_unique();
+        let position_info = PositionInfo::zeroed();
+        let resume = |state| {
+            resume_position_ixs(authority, Farms::RayUsdcVault, 0, 0, state, &position_info)
+                .map(|ixs| ixs.len())
+        };
+        assert_eq!(resume(Position::Opening), Ok(3));
+        assert_eq!(resume(Position::TopUp), Ok(3));
+        assert_eq!(resume(Position::Swapped), Ok(2));
+        assert_eq!(resume(Position::TopUpSwapped), Ok(2));
+        assert_eq!(resume(Position::AddedLiquidity), Ok(1));
+        assert_eq!(
+            resume(Position::RemovedLiquidity),
+            Err(PlannerError::UnsupportedCloseStep {
+                state: Position::RemovedLiquidity,
+                next: &[Position::SwappedForRepaying],
+            })
+        );
+        assert!(matches!(
+            resume(Position::Opened),
+            Err(PlannerError::CannotResume { next, .. }) if next.contains(&Position::Withdrawing)
+        ));
+
+        let mut user_farm = UserFarm::zeroed();
+        user_farm.user_farm_number = 1;
+        user_farm.number_of_obligations = 2;
+        user_farm.obligations[1].obligation_account = Pubkey::new_unique();
+        user_farm.obligations[1].position_state = Position::Swapped as u8;
+        let ixs = new_resume_position_ixs(
+            authority,
+            Farms::RayUsdcVault,
+            1,
+            &user_farm,
+            &position_info,
+        )
+        .unwrap();
+        assert_eq!(ixs.len(), 2);
+        // the instructions use the user farm the obligation belongs to
+        let user_farm_address =
+            derive_user_farm_address(authority, crate::ID, 1, Farms::RayUsdcVault).0;
+        assert!(ixs[0]
+            .accounts
+            .iter()
+            .any(|account| account.pubkey == user_farm_address));
+        assert_eq!(
+            new_resume_position_ixs(
+                authority,
+                Farms::RayUsdcVault,
+                MAX_OBLIGATIONS,
+                &user_farm,
+                &position_info
+            ),
+            Err(PlannerError::InvalidObligationIndex(MAX_OBLIGATIONS))
+        );
+    }
+
+    #[test]
+    fn test_resume_unused_obligation() {
+        let authority = Pubkey::new_unique();
+        let position_info = PositionInfo::zeroed();
+        let mut user_farm = UserFarm::zeroed();
+        user_farm.number_of_obligations = 1;
+        user_farm.obligations[0].obligation_account = Pubkey::new_unique();
+        let resume = |user_farm: &UserFarm, obligation_index| {
+            new_resume_position_ixs(
+                authority,
+                Farms::RayUsdcVault,
+                obligation_index,
+                user_farm,
+                &position_info,
+            )
+            .map(|ixs| ixs.len())
+        };
+        // a created obligation which hasn't deposited anything, and one which wasn't created
+        assert_eq!(resume(&user_farm, 0), Err(PlannerError::NoPosition(0)));
+        assert_eq!(resume(&user_farm, 1), Err(PlannerError::NoPosition(1)));
+
+        user_farm.obligations[0].coin_amount = 100;
+        assert_eq!(resume(&user_farm, 0), Ok(3));
+    }
+
+    #[test]
+    fn test_resume_orca_position() {
+        let authority = Pubkey::new_unique();
+        let mut position_info = PositionInfo::zeroed();
+        let resume = |state, position_info: &PositionInfo| {
+            resume_position_ixs(authority, Farms::OrcaUsdcVault, 0, 0, state, position_info)
+        };
+        assert_eq!(resume(Position::Swapped, &position_info).unwrap().len(), 1);
+        assert_eq!(
+            resume(Position::Opening, &position_info),
+            Err(PlannerError::UnknownSwapAccounts(Farms::OrcaUsdcVault))
+        );
+
+        assert_eq!(
+            resume(Position::WithdrawnOrcaDoubleDip, &position_info),
+            Err(PlannerError::InvalidWithdrawPercent(0))
+        );
+        position_info.withdraw_percent = 40;
+        position_info.close_method = 1;
+        let ixs = resume(Position::WithdrawnOrcaDoubleDip, &position_info).unwrap();
+        assert_eq!(ixs[0].data[8..], [0, 40, 1]);
+    }
+}
@@ -0,0 +1,269 @@
+//! quotes the constant product swaps made by `swap_tokens_raydium_stats` and
+//! `swap_tokens_orca_stats` when opening or closing a position.
+//!
+//! quotes are computed from the balances of the pool's coin and pc token accounts. raydium
+//! amm v4 pools may hold part of their liquidity in serum open orders, so quotes against
+//! them are a close, but not exact, estimate of the swap performed on-chain.
+//!
+//! the leveraged farm program doesn't document the units of the farm's `buy_slip` and
+//! `sell_slip`, so the minimum amounts out take a `Slippage` whose denominator is always
+//! given by the caller.
+
+use super::error::SwapQuoteError;
+use crate::accounts::leveraged_farm::LeveragedFarm;
+use anchor_lang::solana_program::program_pack::Pack;
+use tulipv2_sdk_common::math::{common::TryDiv, decimal::Decimal};
+
+/// denominator of slippage expressed in basis points
+pub const SLIPPAGE_DENOMINATOR: u64 = 10_000;
+
+/// the fee charged on the input amount of a swap
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapFees {
+    pub numerator: u64,
+    pub denominator: u64,
+}
+
+impl SwapFees {
+    /// 0.25% trade fee of raydium amm v4 pools
+    pub const RAYDIUM_V4: SwapFees = SwapFees {
+        numerator: 25,
+        denominator: 10_000,
+    };
+    /// 0.25% trade fee and 0.05% owner fee of orca token swap pools
+    pub const ORCA: SwapFees = SwapFees {
+        numerator: 30,
+        denominator: 10_000,
+    };
+}
+
+/// the fraction by which the amount out of a swap may be less than quoted
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Slippage {
+    pub numerator: u64,
+    pub denominator: u64,
+}
+
+impl Slippage {
+    /// slippage of `bps` basis points
+    pub fn bps(bps: u64) -> Slippage {
+        Slippage {
+            numerator: bps,
+            denominator: SLIPPAGE_DENOMINATOR,
+        }
+    }
+
+    /// the leveraged farm's `sell_slip` or `buy_slip` for swaps in the given direction,
+    /// over `denominator`, which the caller must know the farm's settings are stored in
+    pub fn farm(
+        leveraged_farm: &LeveragedFarm,
+        direction: SwapDirection,
+        denominator: u64,
+    ) -> Slippage {
+        Slippage {
+            numerator: match direction {
+                SwapDirection::CoinToPc => leveraged_farm.sell_slip,
+                SwapDirection::PcToCoin => leveraged_farm.buy_slip,
+            },
+            denominator,
+        }
+    }
+}
+
+/// the direction of a swap between the coin and pc tokens of a pool
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwapDirection {
+    /// sells coin for pc, bounded by the leveraged farm's `sell_slip`
+    CoinToPc,
+    /// buys coin with pc, bounded by the leveraged farm's `buy_slip`
+    PcToCoin,
+}
+
+/// the coin and pc balances of a constant product pool
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolBalances {
+    pub coin_amount: u64,
+    pub pc_amount: u64,
+    pub fees: SwapFees,
+}
+
+/// the result of swapping `amount_in` against a pool
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapQuote {
+    pub amount_in: u64,
+    /// portion of `amount_in` kept by the pool as fees
+    pub fee_amount: u64,
+    pub amount_out: u64,
+    /// fraction by which `amount_out` is less than the output at the pool's spot price, after fees
+    pub price_impact: Decimal,
+}
+
+impl PoolBalances {
+    /// decodes the balances of the pool's coin and pc spl token accounts
+    pub fn from_token_accounts(
+        coin_token_account: &[u8],
+        pc_token_account: &[u8],
+        fees: SwapFees,
+    ) -> std::result::Result<Self, SwapQuoteError> {
+        let unpack = |data: &[u8]| {
+            spl_token::state::Account::unpack(data)
+                .map(|account| account.amount)
+                .map_err(|_| SwapQuoteError::InvalidTokenAccount)
+        };
+        Ok(Self {
+            coin_amount: unpack(coin_token_account)?,
+            pc_amount: unpack(pc_token_account)?,
+            fees,
+        })
+    }
+
+    /// quotes swapping `amount_in` of the input token in the given direction
+    pub fn quote(
+        &self,
+        direction: SwapDirection,
+        amount_in: u64,
+    ) -> std::result::Result<SwapQuote, SwapQuoteError> {
+        let (reserve_in, reserve_out) = match direction {
+            SwapDirection::CoinToPc => (self.coin_amount, self.pc_amount),
+            SwapDirection::PcToCoin => (self.pc_amount, self.coin_amount),
+        };
+        if reserve_in == 0 || reserve_out == 0 {
+            return Err(SwapQuoteError::EmptyPool);
+        }
+        if self.fees.denominator == 0 || self.fees.numerator > self.fees.denominator {
+            return Err(SwapQuoteError::InvalidFees);
+        }
+        // fees are rounded up, in favour of the pool
+        let fee_amount = (amount_in as u128 * self.fees.numerator as u128)
+            .checked_add(self.fees.denominator as u128 - 1)
+            .ok_or(SwapQuoteError::MathOverflow)?
+            / self.fees.denominator as u128;
+        let amount_in_after_fees = amount_in as u128 - fee_amount;
+        let new_reserve_in = reserve_in as u128 + amount_in_after_fees;
+        let amount_out = reserve_out as u128 * amount_in_after_fees / new_reserve_in;
+        let price_impact = Decimal::from(amount_in_after_fees)
+            .try_div(Decimal::from(new_reserve_in))
+            .map_err(|_| SwapQuoteError::MathOverflow)?;
+        Ok(SwapQuote {
+            amount_in,
+            fee_amount: fee_amount as u64,
+            // bounded by reserve_out
+            amount_out: amount_out as u64,
+            price_impact,
+        })
+    }
+}
+
+impl SwapQuote {
+    /// the minimum amount out accepted with the given slippage
+    pub fn min_amount_out(&self, slippage: Slippage) -> std::result::Result<u64, SwapQuoteError> {
+        if slippage.denominator == 0 || slippage.numerator > slippage.denominator {
+            return Err(SwapQuoteError::InvalidSlippage(
+                slippage.numerator,
+                slippage.denominator,
+            ));
+        }
+        Ok(
+            (self.amount_out as u128 * (slippage.denominator - slippage.numerator) as u128
+                / slippage.denominator as u128) as u64,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bytemuck::Zeroable;
+
+    fn token_account(amount: u64) -> Vec<u8> {
+        let mut data = vec![0_u8; spl_token::state::Account::LEN];
+        spl_token::state::Account::pack(
+            spl_token::state::Account {
+                amount,
+                state: spl_token::state::AccountState::Initialized,
+                ..Default::default()
+            },
+            &mut data,
+        )
+        .unwrap();
+        data
+    }
+
+    #[test]
+    fn test_quote() {
+        let pool = PoolBalances::from_token_accounts(
+            &token_account(1_000_000),
+            &token_account(2_000_000),
+            SwapFees::RAYDIUM_V4,
+        )
+        .unwrap();
+        assert_eq!(pool.coin_amount, 1_000_000);
+
+        let quote = pool.quote(SwapDirection::CoinToPc, 10_000).unwrap();
+        assert_eq!(quote.fee_amount, 25);
+        // 2_000_000 * 9_975 / 1_009_975
+        assert_eq!(quote.amount_out, 19_752);
+        assert!(quote.price_impact < Decimal::from_percent(1));
+        let quote = pool.quote(SwapDirection::PcToCoin, 1_000_000).unwrap();
+        // the pool's pc balance grows by half, moving the price by around a third
+        assert!(quote.price_impact > Decimal::from_percent(33));
+        assert!(quote.amount_out < 333_333);
+
+        let orca = PoolBalances {
+            fees: SwapFees::ORCA,
+            ..pool
+        };
+        assert_eq!(
+            orca.quote(SwapDirection::CoinToPc, 10_000)
+                .unwrap()
+                .fee_amount,
+            30
+        );
+        assert_eq!(
+            PoolBalances {
+                coin_amount: 0,
+                ..pool
+            }
+            .quote(SwapDirection::CoinToPc, 1),
+            Err(SwapQuoteError::EmptyPool)
+        );
+        assert_eq!(
+            PoolBalances::from_token_accounts(&[0; 10], &token_account(1), SwapFees::ORCA),
+            Err(SwapQuoteError::InvalidTokenAccount)
+        );
+    }
+
+    #[test]
+    fn test_min_amount_out() {
+        let quote = SwapQuote {
+            amount_in: 0,
+            fee_amount: 0,
+            amount_out: 10_000,
+            price_impact: Decimal::zero(),
+        };
+        assert_eq!(quote.min_amount_out(Slippage::bps(50)), Ok(9_950));
+        assert_eq!(
+            quote.min_amount_out(Slippage::bps(SLIPPAGE_DENOMINATOR + 1)),
+            Err(SwapQuoteError::InvalidSlippage(
+                SLIPPAGE_DENOMINATOR + 1,
+                SLIPPAGE_DENOMINATOR
+            ))
+        );
+
+        let mut leveraged_farm = LeveragedFarm::zeroed();
+        leveraged_farm.buy_slip = 100;
+        leveraged_farm.sell_slip = 3;
+        let buy = Slippage::farm(
+            &leveraged_farm,
+            SwapDirection::PcToCoin,
+            SLIPPAGE_DENOMINATOR,
+        );
+        assert_eq!(quote.min_amount_out(buy), Ok(9_900));
+        let sell = Slippage::farm(&leveraged_farm, SwapDirection::CoinToPc, 1_000);
+        assert_eq!(quote.min_amount_out(sell), Ok(9_970));
+        assert_eq!(
+            quote.min_amount_out(Slippage::farm(&leveraged_farm, SwapDirection::CoinToPc, 0)),
+            Err(SwapQuoteError::InvalidSlippage(3, 0))
+        );
+    }
+}
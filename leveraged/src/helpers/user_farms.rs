@@ -0,0 +1,265 @@
+//! discovers the user farms and obligations an authority holds across leveraged farms.
+//!
+//! each farm has its own series of user farm accounts, derived from the farm and the user
+//! farm's index, with the first account tracking how many user farms exist. every user farm
+//! manages up to 3 obligations, so the next free slot is the first obligation of an existing
+//! user farm which is unused, closed or liquidated, or the first obligation of a new user farm.
+
+use super::error::{ConversionError, DiscoveryError, UserFarmError};
+use crate::accounts::{
+    derivations::derive_user_farm_address, user_farm::UserFarm, Farms, Position,
+};
+use anchor_lang::prelude::Pubkey;
+
+/// fetches account data, such as through an rpc client or from an in-memory cache
+pub trait AccountFetcher {
+    type Error;
+    /// returns the data of the account at `address`, or `None` if it does not exist
+    fn fetch(&self, address: &Pubkey) -> std::result::Result<Option<Vec<u8>>, Self::Error>;
+}
+
+/// an obligation which holds, or is in the process of opening or closing, a position.
+/// obligations in a state unknown to this version of the sdk are treated as live
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LiveObligation {
+    pub farm: Farms,
+    pub user_farm: Pubkey,
+    pub user_farm_index: u64,
+    pub obligation_index: u8,
+    pub obligation_account: Pubkey,
+    /// the raw position state byte, see `LiveObligation::state`
+    pub position_state: u8,
+    pub coin_amount: u64,
+    pub pc_amount: u64,
+    pub deposited_lp_tokens: u64,
+}
+
+impl LiveObligation {
+    /// returns the obligation's position state, or `ConversionError::UnknownPosition`
+    /// for a state unknown to this version of the sdk
+    pub fn state(&self) -> std::result::Result<Position, ConversionError> {
+        Position::try_from(self.position_state)
+    }
+}
+
+/// the slot a new position in a farm can be opened in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FreeSlot {
+    pub user_farm_index: u64,
+    pub obligation_index: u8,
+    /// whether the user farm account needs to be created before opening the position
+    pub create_user_farm: bool,
+    /// whether the obligation account needs to be created before opening the position
+    pub create_obligation: bool,
+}
+
+/// the live obligations of an authority in a farm, and the next slot a position can be opened in
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FarmPositions {
+    pub farm: Farms,
+    pub obligations: Vec<LiveObligation>,
+    pub next_free_slot: FreeSlot,
+}
+
+/// discovers the positions of `authority` in every known farm
+pub fn discover_all_positions<F: AccountFetcher>(
+    fetcher: &F,
+    authority: Pubkey,
+) -> std::result::Result<Vec<FarmPositions>, DiscoveryError<F::Error>> {
+    (0_u64..)
+        .map_while(|farm| Farms::try_from(farm).ok())
+        .map(|farm| discover_positions(fetcher, authority, farm))
+        .collect()
+}
+
+/// walks every user farm of `authority` in `farm`, returning its live obligations and the
+/// next free slot
+pub fn discover_positions<F: AccountFetcher>(
+    fetcher: &F,
+    authority: Pubkey,
+    farm: Farms,
+) -> std::result::Result<FarmPositions, DiscoveryError<F::Error>> {
+    let mut obligations = Vec::new();
+    let mut next_free_slot = None;
+    let mut user_farm_index = 0;
+    // the first user farm tracks how many exist, and is fetched before the count is known
+    let mut number_of_user_farms = 1;
+    while user_farm_index < number_of_user_farms {
+        let address = derive_user_farm_address(authority, crate::ID, user_farm_index, farm).0;
+        let user_farm = match fetch_user_farm(fetcher, &address)? {
+            Some(user_farm) => user_farm,
+            None => {
+                next_free_slot.get_or_insert(FreeSlot {
+                    user_farm_index,
+                    obligation_index: 0,
+                    create_user_farm: true,
+                    create_obligation: true,
+                });
+                break;
+            }
+        };
+        if user_farm_index == 0 {
+            number_of_user_farms = (user_farm.number_of_user_farms as u64).max(1);
+        }
+        for (obligation_index, obligation) in user_farm.obligations.iter().enumerate() {
+            let obligation_index = obligation_index as u8;
+            let obligation_exists = obligation_index < user_farm.number_of_obligations;
+            if obligation_exists
+                && !matches!(
+                    obligation.state(),
+                    Ok(Position::Closed | Position::Liquidated)
+                )
+            {
+                obligations.push(LiveObligation {
+                    farm,
+                    user_farm: address,
+                    user_farm_index,
+                    obligation_index,
+                    obligation_account: obligation.obligation_account,
+                    position_state: obligation.position_state,
+                    coin_amount: obligation.coin_amount,
+                    pc_amount: obligation.pc_amount,
+                    deposited_lp_tokens: obligation.deposited_lp_tokens,
+                });
+                continue;
+            }
+            next_free_slot.get_or_insert(FreeSlot {
+                user_farm_index,
+                obligation_index,
+                create_user_farm: false,
+                create_obligation: !obligation_exists,
+            });
+        }
+        user_farm_index += 1;
+    }
+    Ok(FarmPositions {
+        farm,
+        obligations,
+        next_free_slot: next_free_slot.unwrap_or(FreeSlot {
+            user_farm_index,
+            obligation_index: 0,
+            create_user_farm: true,
+            create_obligation: true,
+        }),
+    })
+}
+
+fn fetch_user_farm<F: AccountFetcher>(
+    fetcher: &F,
+    address: &Pubkey,
+) -> std::result::Result<Option<UserFarm>, DiscoveryError<F::Error>> {
+    match fetcher
+        .fetch(address)
+        .map_err(|err| DiscoveryError::Fetch(*address, err))?
+    {
+        Some(data) => UserFarm::try_from_account_data(&data)
+            .map(Some)
+            .map_err(|err: UserFarmError| DiscoveryError::UserFarm(*address, err)),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::accounts::USER_FARM_ACCOUNT_SIZE;
+    use anchor_lang::Discriminator;
+    use std::collections::HashMap;
+
+    impl AccountFetcher for HashMap<Pubkey, Vec<u8>> {
+        type Error = ();
+        fn fetch(&self, address: &Pubkey) -> std::result::Result<Option<Vec<u8>>, ()> {
+            Ok(self.get(address).cloned())
+        }
+    }
+
+    fn user_farm_data(
+        number_of_user_farms: u8,
+        number_of_obligations: u8,
+        states: [Position; 3],
+    ) -> Vec<u8> {
+        let mut data = vec![0_u8; USER_FARM_ACCOUNT_SIZE];
+        data[..8].copy_from_slice(&UserFarm::discriminator());
+        data[73] = number_of_obligations;
+        data[74] = number_of_user_farms;
+        for (index, state) in states.iter().enumerate() {
+            data[76 + index * 57 + 56] = *state as u8;
+        }
+        data
+    }
+
+    #[test]
+    fn test_discover_positions() {
+        let authority = Pubkey::new_unique();
+        let farm = Farms::RayUsdcVault;
+        let address = |index| derive_user_farm_address(authority, crate::ID, index, farm).0;
+        let mut accounts = HashMap::new();
+
+        let positions = discover_positions(&accounts, authority, farm).unwrap();
+        assert!(positions.obligations.is_empty());
+        assert_eq!(
+            positions.next_free_slot,
+            FreeSlot {
+                user_farm_index: 0,
+                obligation_index: 0,
+                create_user_farm: true,
+                create_obligation: true,
+            }
+        );
+
+        // the first user farm is full, the second has a closed obligation
+        accounts.insert(
+            address(0),
+            user_farm_data(
+                2,
+                3,
+                [Position::Opened, Position::Swapped, Position::Opened],
+            ),
+        );
+        accounts.insert(
+            address(1),
+            user_farm_data(
+                0,
+                2,
+                [Position::Opened, Position::Closed, Position::Opening],
+            ),
+        );
+        let positions = discover_positions(&accounts, authority, farm).unwrap();
+        assert_eq!(positions.obligations.len(), 4);
+        assert_eq!(positions.obligations[1].state(), Ok(Position::Swapped));
+        assert_eq!(positions.obligations[3].user_farm, address(1));
+        assert_eq!(
+            positions.next_free_slot,
+            FreeSlot {
+                user_farm_index: 1,
+                obligation_index: 1,
+                create_user_farm: false,
+                create_obligation: false,
+            }
+        );
+
+        accounts.insert(address(1), user_farm_data(0, 3, [Position::Opened; 3]));
+        let positions = discover_positions(&accounts, authority, farm).unwrap();
+        assert_eq!(positions.obligations.len(), 6);
+        assert_eq!(positions.next_free_slot.user_farm_index, 2);
+        assert!(positions.next_free_slot.create_user_farm);
+
+        // a state added by a newer program doesn't prevent the user farm from being read
+        let mut unknown = user_farm_data(1, 1, [Position::Opened; 3]);
+        unknown[76 + 56] = 200;
+        accounts.insert(address(0), unknown);
+        let positions = discover_positions(&accounts, authority, farm).unwrap();
+        assert_eq!(positions.obligations.len(), 1);
+        assert_eq!(positions.obligations[0].position_state, 200);
+        assert_eq!(
+            positions.obligations[0].state(),
+            Err(ConversionError::UnknownPosition(200))
+        );
+        assert_eq!(
+            discover_all_positions(&HashMap::new(), authority)
+                .unwrap()
+                .len(),
+            Farms::RaywhEthRayVault as usize + 1
+        );
+    }
+}
@@ -13,10 +13,25 @@ pub struct ClosePositionInfoAccount<'info> {
 pub fn close_position_info_account<'info>(
     accounts: ClosePositionInfoAccount<'info>,
 ) -> Option<Instruction> {
+    close_position_info_account_ix(accounts.to_account_metas(None))
+}
+
+/// builds the `close_position_info_account` instruction off-chain
+pub fn close_position_info_account_keys(
+    authority: Pubkey,
+    position_info_account: Pubkey,
+) -> Option<Instruction> {
+    close_position_info_account_ix(vec![
+        AccountMeta::new(authority, true),
+        AccountMeta::new(position_info_account, false),
+    ])
+}
+
+fn close_position_info_account_ix(accounts: Vec<AccountMeta>) -> Option<Instruction> {
     let ix_sighash = GlobalSighashDB.get_deprecated("close_position_info_account")?;
     Some(Instruction {
         program_id: crate::ID,
-        accounts: accounts.to_account_metas(None),
+        accounts,
         data: ix_sighash.to_vec(),
     })
 }
@@ -72,25 +72,93 @@ pub fn swap_tokens_orca_stats<'info>(
     lp_mint: &AccountInfo<'info>,
     position_info_account: &AccountInfo<'info>,
     obligation_index: u8,
+) -> Option<Instruction> {
+    swap_tokens_orca_stats_ix(
+        accounts.to_account_metas(None),
+        serum_fee_recipient.key(),
+        lending_market_account.key(),
+        lending_market_authority.key(),
+        lending_program.key(),
+        lp_mint.key(),
+        position_info_account.key(),
+        obligation_index,
+    )
+}
+
+/// accounts of the `swap_tokens_orca_stats` instruction, which can be used
+/// to build the instruction off-chain.
+///
+/// orca farms store their swap in the serum fields of `LevFarmConfig`, so `market`
+/// is the orca swap account, `coin_vault` and `pc_vault` are the pool's token accounts
+/// and `market_vault_signer` is the swap authority
+pub struct OrcaSwap {
+    pub authority: Pubkey,
+    pub leveraged_farm: Pubkey,
+    pub user_farm: Pubkey,
+    pub user_farm_obligation: Pubkey,
+    pub pc_wallet: Pubkey,
+    pub market: Pubkey,
+    pub open_orders: Pubkey,
+    pub request_queue: Pubkey,
+    pub event_queue: Pubkey,
+    pub bids: Pubkey,
+    pub asks: Pubkey,
+    pub order_payer_token_account: Pubkey,
+    pub coin_vault: Pubkey,
+    pub pc_vault: Pubkey,
+    pub market_vault_signer: Pubkey,
+    pub coin_wallet: Pubkey,
+    pub token_program: Pubkey,
+    pub rent: Pubkey,
+    pub dex_program: Pubkey,
+    pub vault_signer: Pubkey,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn swap_tokens_orca_stats_keys(
+    accounts: Box<OrcaSwap>,
+    serum_fee_recipient: Pubkey,
+    lending_market_account: Pubkey,
+    lending_market_authority: Pubkey,
+    lending_program: Pubkey,
+    lp_mint: Pubkey,
+    position_info_account: Pubkey,
+    obligation_index: u8,
+) -> Option<Instruction> {
+    swap_tokens_orca_stats_ix(
+        accounts.to_account_metas(None),
+        serum_fee_recipient,
+        lending_market_account,
+        lending_market_authority,
+        lending_program,
+        lp_mint,
+        position_info_account,
+        obligation_index,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn swap_tokens_orca_stats_ix(
+    mut accounts: Vec<AccountMeta>,
+    serum_fee_recipient: Pubkey,
+    lending_market_account: Pubkey,
+    lending_market_authority: Pubkey,
+    lending_program: Pubkey,
+    lp_mint: Pubkey,
+    position_info_account: Pubkey,
+    obligation_index: u8,
 ) -> Option<Instruction> {
     let ix_sighash = GlobalSighashDB.get_deprecated("swap_tokens_orca_stats")?;
     let mut ix_data = Vec::with_capacity(9);
     ix_data.extend_from_slice(&ix_sighash[..]);
     ix_data.extend_from_slice(&AnchorSerialize::try_to_vec(&obligation_index).unwrap());
 
-    let mut accounts = accounts.to_account_metas(None);
-    accounts.push(AccountMeta::new(serum_fee_recipient.key(), false));
-    accounts.push(AccountMeta::new_readonly(
-        lending_market_account.key(),
-        false,
-    ));
-    accounts.push(AccountMeta::new_readonly(
-        lending_market_authority.key(),
-        false,
-    ));
-    accounts.push(AccountMeta::new_readonly(lending_program.key(), false));
-    accounts.push(AccountMeta::new(lp_mint.key(), false));
-    accounts.push(AccountMeta::new(position_info_account.key(), false));
+    accounts.push(AccountMeta::new(serum_fee_recipient, false));
+    accounts.push(AccountMeta::new_readonly(lending_market_account, false));
+    accounts.push(AccountMeta::new_readonly(lending_market_authority, false));
+    accounts.push(AccountMeta::new_readonly(lending_program, false));
+    accounts.push(AccountMeta::new(lp_mint, false));
+    accounts.push(AccountMeta::new(position_info_account, false));
 
     Some(Instruction {
         program_id: crate::ID,
@@ -98,3 +166,30 @@ pub fn swap_tokens_orca_stats<'info>(
         data: ix_data,
     })
 }
+
+impl ToAccountMetas for OrcaSwap {
+    fn to_account_metas(&self, _is_signer: Option<bool>) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new(self.leveraged_farm, false),
+            AccountMeta::new(self.user_farm, false),
+            AccountMeta::new(self.user_farm_obligation, false),
+            AccountMeta::new(self.pc_wallet, false),
+            AccountMeta::new(self.market, false),
+            AccountMeta::new(self.open_orders, false),
+            AccountMeta::new(self.request_queue, false),
+            AccountMeta::new(self.event_queue, false),
+            AccountMeta::new(self.bids, false),
+            AccountMeta::new(self.asks, false),
+            AccountMeta::new(self.order_payer_token_account, false),
+            AccountMeta::new(self.coin_vault, false),
+            AccountMeta::new(self.pc_vault, false),
+            AccountMeta::new_readonly(self.market_vault_signer, false),
+            AccountMeta::new(self.coin_wallet, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.rent, false),
+            AccountMeta::new_readonly(self.dex_program, false),
+            AccountMeta::new_readonly(self.vault_signer, false),
+        ]
+    }
+}
@@ -58,6 +58,82 @@ pub fn withdraw_orca_vault_dd_close<'info>(
     obligation_index: u8,
     withdraw_percent: u8,
     close_method: u8,
+) -> Option<Instruction> {
+    withdraw_orca_vault_dd_close_ix(
+        accounts.to_account_metas(None),
+        lending_market_account.key(),
+        user_farm_obligation.key(),
+        lending_market_authority.key(),
+        lending_program.key(),
+        position_info_account.key(),
+        obligation_index,
+        withdraw_percent,
+        close_method,
+    )
+}
+
+/// accounts of the `withdraw_orca_vault_dd_close` instruction, which can be used
+/// to build the instruction off-chain
+pub struct WithdrawOrcaFarmDoubleDip {
+    pub authority: Pubkey,
+    pub vault_account: Pubkey,
+    pub vault_user_account: Pubkey,
+    pub token_program: Pubkey,
+    pub rent: Pubkey,
+    pub vault_pda: Pubkey,
+    pub system_program: Pubkey,
+    pub user_farm_token_account: Pubkey,
+    pub user_farm_dd_token_account: Pubkey,
+    pub user_reward_dd_token_account: Pubkey,
+    pub global_base_dd_token_vault: Pubkey,
+    pub farm_dd_token_mint: Pubkey,
+    pub global_farm_dd: Pubkey,
+    pub user_farm_dd: Pubkey,
+    pub global_reward_dd_token_vault: Pubkey,
+    pub convert_authority_dd: Pubkey,
+    pub aqua_farm_program: Pubkey,
+    pub leveraged_user_farm: Pubkey,
+    pub leveraged_farm: Pubkey,
+    pub solfarm_vault_program: Pubkey,
+    pub obligation_vault_address: Pubkey,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw_orca_vault_dd_close_keys(
+    accounts: Box<WithdrawOrcaFarmDoubleDip>,
+    lending_market_account: Pubkey,
+    user_farm_obligation: Pubkey,
+    lending_market_authority: Pubkey,
+    lending_program: Pubkey,
+    position_info_account: Pubkey,
+    obligation_index: u8,
+    withdraw_percent: u8,
+    close_method: u8,
+) -> Option<Instruction> {
+    withdraw_orca_vault_dd_close_ix(
+        accounts.to_account_metas(None),
+        lending_market_account,
+        user_farm_obligation,
+        lending_market_authority,
+        lending_program,
+        position_info_account,
+        obligation_index,
+        withdraw_percent,
+        close_method,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn withdraw_orca_vault_dd_close_ix(
+    mut accounts: Vec<AccountMeta>,
+    lending_market_account: Pubkey,
+    user_farm_obligation: Pubkey,
+    lending_market_authority: Pubkey,
+    lending_program: Pubkey,
+    position_info_account: Pubkey,
+    obligation_index: u8,
+    withdraw_percent: u8,
+    close_method: u8,
 ) -> Option<Instruction> {
     let ix_sighash = GlobalSighashDB.get_deprecated("withdraw_orca_vault_dd_close")?;
     let mut ix_data = Vec::with_capacity(11);
@@ -66,18 +142,11 @@ pub fn withdraw_orca_vault_dd_close<'info>(
     ix_data.extend_from_slice(&AnchorSerialize::try_to_vec(&withdraw_percent).unwrap());
     ix_data.extend_from_slice(&AnchorSerialize::try_to_vec(&close_method).unwrap());
 
-    let mut accounts = accounts.to_account_metas(None);
-    accounts.push(AccountMeta::new_readonly(
-        lending_market_account.key(),
-        false,
-    ));
-    accounts.push(AccountMeta::new(user_farm_obligation.key(), false));
-    accounts.push(AccountMeta::new_readonly(
-        lending_market_authority.key(),
-        false,
-    ));
-    accounts.push(AccountMeta::new_readonly(lending_program.key(), false));
-    accounts.push(AccountMeta::new(position_info_account.key(), false));
+    accounts.push(AccountMeta::new_readonly(lending_market_account, false));
+    accounts.push(AccountMeta::new(user_farm_obligation, false));
+    accounts.push(AccountMeta::new_readonly(lending_market_authority, false));
+    accounts.push(AccountMeta::new_readonly(lending_program, false));
+    accounts.push(AccountMeta::new(position_info_account, false));
 
     Some(Instruction {
         program_id: crate::ID,
@@ -85,3 +154,31 @@ pub fn withdraw_orca_vault_dd_close<'info>(
         data: ix_data,
     })
 }
+
+impl ToAccountMetas for WithdrawOrcaFarmDoubleDip {
+    fn to_account_metas(&self, _is_signer: Option<bool>) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.authority, true),
+            AccountMeta::new(self.vault_account, false),
+            AccountMeta::new(self.vault_user_account, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.rent, false),
+            AccountMeta::new(self.vault_pda, false),
+            AccountMeta::new_readonly(self.system_program, false),
+            AccountMeta::new(self.user_farm_token_account, false),
+            AccountMeta::new(self.user_farm_dd_token_account, false),
+            AccountMeta::new(self.user_reward_dd_token_account, false),
+            AccountMeta::new(self.global_base_dd_token_vault, false),
+            AccountMeta::new(self.farm_dd_token_mint, false),
+            AccountMeta::new(self.global_farm_dd, false),
+            AccountMeta::new(self.user_farm_dd, false),
+            AccountMeta::new(self.global_reward_dd_token_vault, false),
+            AccountMeta::new_readonly(self.convert_authority_dd, false),
+            AccountMeta::new_readonly(self.aqua_farm_program, false),
+            AccountMeta::new(self.leveraged_user_farm, false),
+            AccountMeta::new(self.leveraged_farm, false),
+            AccountMeta::new_readonly(self.solfarm_vault_program, false),
+            AccountMeta::new(self.obligation_vault_address, false),
+        ]
+    }
+}
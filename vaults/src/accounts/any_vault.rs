@@ -0,0 +1,200 @@
+//! decodes the account data of any v2 vault type, detecting the type of vault from
+//! the account's discriminator
+
+use super::{
+    atrix_vault::{AtrixVaultV1, ATRIX_VAULT_ACCOUNT_SIZE},
+    lending_optimizer::{LendingOptimizerV1, OPTIMIZER_VAULT_ACCOUNT_SIZE},
+    multi_optimizer::{MultiDepositOptimizerV1, MULTI_DEPOSIT_OPTIMIZER_ACCOUNT_SIZE},
+    orca_vault::{
+        OrcaDoubleDipVaultV1, OrcaVaultV1, ORCA_DOUBLE_DIP_VAULT_ACCOUNT_SIZE,
+        ORCA_VAULT_ACCOUNT_SIZE,
+    },
+    quarry_vault::{QuarryVaultV1, QUARRY_VAULT_ACCOUNT_SIZE},
+    raydium_vault::{RaydiumVaultV1, VAULT_ACCOUNT_SIZE},
+    vault_base::VaultBaseV1,
+    Base, InitVaultArgsV1,
+};
+use anchor_lang::{prelude::*, Discriminator};
+use anchor_spl::token::Mint;
+use bytemuck::Pod;
+use thiserror::Error;
+use tulipv2_sdk_common::traits::vault::TokenizedShares;
+use tulipv2_sdk_farms::Farm;
+
+/// any of the vault account types owned by the v2 vaults program
+#[derive(Clone, Copy)]
+pub enum AnyVault {
+    Raydium(RaydiumVaultV1),
+    Orca(OrcaVaultV1),
+    OrcaDoubleDip(OrcaDoubleDipVaultV1),
+    Quarry(QuarryVaultV1),
+    Atrix(AtrixVaultV1),
+    LendingOptimizer(LendingOptimizerV1),
+    MultiDepositOptimizer(MultiDepositOptimizerV1),
+}
+
+/// errors returned when decoding vault account data
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum DecodeVaultError {
+    #[error("account data is shorter than a discriminator")]
+    MissingDiscriminator,
+    #[error("discriminator {0:?} is not that of a vault account")]
+    UnknownDiscriminator([u8; 8]),
+    #[error("invalid account size {actual}, expected {expected}")]
+    InvalidSize { expected: usize, actual: usize },
+}
+
+impl From<DecodeVaultError> for ProgramError {
+    fn from(_: DecodeVaultError) -> Self {
+        ProgramError::InvalidAccountData
+    }
+}
+
+/// decodes the data of a vault account, including its discriminator.
+///
+/// returns an error if the data is not that of a vault account, or its size does
+/// not match the size of the vault type given by its discriminator
+pub fn decode_vault(data: &[u8]) -> std::result::Result<AnyVault, DecodeVaultError> {
+    if data.len() < 8 {
+        return Err(DecodeVaultError::MissingDiscriminator);
+    }
+    let mut discriminator = [0_u8; 8];
+    discriminator.copy_from_slice(&data[..8]);
+    if discriminator == RaydiumVaultV1::discriminator() {
+        decode(data, VAULT_ACCOUNT_SIZE).map(AnyVault::Raydium)
+    } else if discriminator == OrcaVaultV1::discriminator() {
+        decode(data, ORCA_VAULT_ACCOUNT_SIZE).map(AnyVault::Orca)
+    } else if discriminator == OrcaDoubleDipVaultV1::discriminator() {
+        decode(data, ORCA_DOUBLE_DIP_VAULT_ACCOUNT_SIZE).map(AnyVault::OrcaDoubleDip)
+    } else if discriminator == QuarryVaultV1::discriminator() {
+        decode(data, QUARRY_VAULT_ACCOUNT_SIZE).map(AnyVault::Quarry)
+    } else if discriminator == AtrixVaultV1::discriminator() {
+        decode(data, ATRIX_VAULT_ACCOUNT_SIZE).map(AnyVault::Atrix)
+    } else if discriminator == LendingOptimizerV1::discriminator() {
+        decode(data, OPTIMIZER_VAULT_ACCOUNT_SIZE).map(AnyVault::LendingOptimizer)
+    } else if discriminator == MultiDepositOptimizerV1::discriminator() {
+        decode(data, MULTI_DEPOSIT_OPTIMIZER_ACCOUNT_SIZE).map(AnyVault::MultiDepositOptimizer)
+    } else {
+        Err(DecodeVaultError::UnknownDiscriminator(discriminator))
+    }
+}
+
+/// copies the account data following the discriminator into `T`. vault types may be
+/// padded beyond their on-chain size, in which case the padding is left zeroed
+fn decode<T: Pod>(data: &[u8], account_size: usize) -> std::result::Result<T, DecodeVaultError> {
+    if data.len() != account_size {
+        return Err(DecodeVaultError::InvalidSize {
+            expected: account_size,
+            actual: data.len(),
+        });
+    }
+    let mut account = T::zeroed();
+    let bytes = bytemuck::bytes_of_mut(&mut account);
+    let len = bytes.len().min(account_size - 8);
+    bytes[..len].copy_from_slice(&data[8..8 + len]);
+    Ok(account)
+}
+
+impl AnyVault {
+    /// returns the vault as a reference to the Base trait
+    pub fn as_base(&self) -> &dyn Base {
+        match self {
+            AnyVault::Raydium(vault) => vault,
+            AnyVault::Orca(vault) => vault,
+            AnyVault::OrcaDoubleDip(vault) => vault,
+            AnyVault::Quarry(vault) => vault,
+            AnyVault::Atrix(vault) => vault,
+            AnyVault::LendingOptimizer(vault) => vault,
+            AnyVault::MultiDepositOptimizer(vault) => vault,
+        }
+    }
+    /// returns the vault as a mutable reference to the Base trait
+    pub fn as_base_mut(&mut self) -> &mut dyn Base {
+        match self {
+            AnyVault::Raydium(vault) => vault,
+            AnyVault::Orca(vault) => vault,
+            AnyVault::OrcaDoubleDip(vault) => vault,
+            AnyVault::Quarry(vault) => vault,
+            AnyVault::Atrix(vault) => vault,
+            AnyVault::LendingOptimizer(vault) => vault,
+            AnyVault::MultiDepositOptimizer(vault) => vault,
+        }
+    }
+}
+
+impl Base for AnyVault {
+    fn base(&self) -> VaultBaseV1 {
+        self.as_base().base()
+    }
+    fn shares_mut(&mut self) -> &mut dyn TokenizedShares {
+        self.as_base_mut().shares_mut()
+    }
+    fn shares(&self) -> &dyn TokenizedShares {
+        self.as_base().shares()
+    }
+    fn init(&mut self, args: &InitVaultArgsV1) {
+        self.as_base_mut().init(args)
+    }
+    fn farm(&self) -> Farm {
+        self.as_base().farm()
+    }
+    fn sync_shares(&mut self, mint: &Mint) {
+        self.as_base_mut().sync_shares(mint)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bytemuck::Zeroable;
+    use tulipv2_sdk_farms::raydium::Raydium;
+
+    fn account_data<T: Pod + Discriminator>(account: &T, account_size: usize) -> Vec<u8> {
+        let mut data = T::discriminator().to_vec();
+        data.extend_from_slice(&bytemuck::bytes_of(account)[..account_size - 8]);
+        data
+    }
+
+    #[test]
+    fn test_decode_vault() {
+        let farm = Farm::Raydium {
+            name: Raydium::RAYUSDC,
+        };
+        let mut raydium = RaydiumVaultV1::zeroed();
+        raydium.base.farm = farm.into();
+        raydium.base.total_shares = 42;
+        let data = account_data(&raydium, VAULT_ACCOUNT_SIZE);
+        let vault = decode_vault(&data).unwrap();
+        assert!(matches!(vault, AnyVault::Raydium(_)));
+        assert_eq!(vault.farm(), farm);
+        assert_eq!(vault.shares().total_shares(), 42);
+
+        let data = account_data(
+            &MultiDepositOptimizerV1::zeroed(),
+            MULTI_DEPOSIT_OPTIMIZER_ACCOUNT_SIZE,
+        );
+        assert!(matches!(
+            decode_vault(&data),
+            Ok(AnyVault::MultiDepositOptimizer(_))
+        ));
+        // a multi deposit optimizer discriminator with a raydium vault sized account
+        let mut data = account_data(&raydium, VAULT_ACCOUNT_SIZE);
+        data[..8].copy_from_slice(&MultiDepositOptimizerV1::discriminator());
+        assert_eq!(
+            decode_vault(&data).err(),
+            Some(DecodeVaultError::InvalidSize {
+                expected: MULTI_DEPOSIT_OPTIMIZER_ACCOUNT_SIZE,
+                actual: VAULT_ACCOUNT_SIZE,
+            })
+        );
+        data[..8].copy_from_slice(&[1_u8; 8]);
+        assert_eq!(
+            decode_vault(&data).err(),
+            Some(DecodeVaultError::UnknownDiscriminator([1_u8; 8]))
+        );
+        assert_eq!(
+            decode_vault(&data[..4]).err(),
+            Some(DecodeVaultError::MissingDiscriminator)
+        );
+    }
+}
@@ -1,16 +1,66 @@
 pub mod atrix;
 pub mod deposit_tracking;
+pub mod multi_deposit_rebalance;
+pub mod multi_deposit_withdraw;
 pub mod orca;
+pub mod orca_withdraw;
 pub mod quarry;
 pub mod raydium;
 use anchor_lang::solana_program::pubkey::Pubkey;
 
-/// base configuration accounts used across all vault types
-pub trait VaultBaseConfig {
-    fn vault(&self) -> Pubkey;
-    fn vault_pda(&self) -> Pubkey;
-    fn withdraw_queue(&self) -> Pubkey;
-    fn compound_queue(&self) -> Pubkey;
-    fn deposit_queue(&self) -> Pubkey;
-    fn shares_mint(&self) -> Pubkey;
+pub use tulipv2_sdk_common::config::deposit_tracking::vault_config::VaultBaseConfig;
+
+#[cfg(test)]
+mod test {
+    use super::raydium::RaydiumVaultConfig;
+    use super::*;
+    use tulipv2_sdk_common::config::deposit_tracking::traits::{
+        IssueShares, RegisterDepositTracking,
+    };
+    use tulipv2_sdk_farms::{raydium::Raydium, Farm};
+
+    #[test]
+    fn test_standalone_deposit_bundle() {
+        let user = Pubkey::new_unique();
+        let farm = Farm::Raydium {
+            name: Raydium::RAYUSDC,
+        };
+        let conf = RaydiumVaultConfig::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            None,
+            None,
+            None,
+            None,
+        );
+        let register = conf.register_deposit_tracking(user);
+
+        let ixs = conf
+            .deposit_bundle(user, conf.underlying_mint, farm, 1_000_000, false)
+            .unwrap();
+        assert_eq!(ixs.len(), 3);
+        assert_eq!(ixs[0].program_id, spl_associated_token_account::id());
+        assert_eq!(
+            ixs[0].accounts[1].pubkey,
+            register.deposit_tracking_hold_account()
+        );
+        assert_eq!(ixs[1], register.instruction(farm).unwrap());
+        assert_eq!(
+            ixs[2],
+            conf.issue_shares(user)
+                .instruction(farm, 1_000_000)
+                .unwrap()
+        );
+
+        let ixs = conf
+            .deposit_bundle(user, conf.underlying_mint, farm, 1_000_000, true)
+            .unwrap();
+        assert_eq!(ixs.len(), 1);
+        assert_eq!(
+            ixs[0],
+            conf.issue_shares(user)
+                .instruction(farm, 1_000_000)
+                .unwrap()
+        );
+    }
 }
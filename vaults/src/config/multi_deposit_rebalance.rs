@@ -0,0 +1,523 @@
+//! off-chain simulation of multi deposit optimizer rebalances.
+//!
+//! each rebalance moves liquidity out of a single standalone vault (vault a) and into another
+//! (vault b), taking the `RebalanceStateTransitionV1` account from `Started`, through
+//! `VaultARemoved` to `VaultABRebalanced`. as the program does, vault a is picked from the
+//! `top_two_deposits` of the optimizer and vault b from its `bottom_two_deposits`, so only
+//! standalone vaults holding deposits are rebalanced, and vaults holding less than the
+//! optimizer's `minimum_rebalance_amount` aren't rebalanced out of.
+//!
+//! the simulator repeatedly takes the pair of candidates, and the amount, which most increase
+//! the interest earned, until no rebalance does, giving the rebalances and the blended supply
+//! apy before and after rebalancing. at most `MAX_SIMULATED_REBALANCES` rebalances are
+//! simulated, and the simulation reports whether it converged before reaching the limit.
+//!
+//! rates are projected with a `RateModel` per standalone vault, keyed by the address of the
+//! standalone vault. `Reserve` implements the rate model of the tulip lending program, which
+//! the spl token lending based platforms share, while other platforms such as mango provide
+//! their own. the projections are expected to include the optimizer's deposits
+
+use crate::accounts::{
+    lending_optimizer::ProgramType,
+    multi_optimizer::{
+        ActiveStandaloneVaults, MultiDepositOptimizerV1, RebalanceStateTransitionV1,
+        RebalanceStates,
+    },
+};
+use anchor_lang::solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use std::collections::HashMap;
+use thiserror::Error;
+use tulipv2_sdk_common::{
+    lending::{rates::ReserveAction, reserve::Reserve},
+    math::rate::Rate,
+    DEFAULT_KEY,
+};
+
+/// the most rebalances simulated, as every rebalance only has to increase the interest earned
+pub const MAX_SIMULATED_REBALANCES: usize = 32;
+
+/// errors returned when simulating a rebalance
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum RebalanceSimulationError {
+    #[error("the vault has no standalone vaults")]
+    NoStandaloneVaults,
+    #[error("no rate model was provided for standalone vault {0}")]
+    MissingRateModel(Pubkey),
+    #[error("failed to project reserve rates: {0}")]
+    RateProjection(#[from] ProgramError),
+    #[error("math overflow")]
+    MathOverflow,
+}
+
+/// projects the supply apy earned on the platform a standalone vault lends to
+pub trait RateModel {
+    /// returns the supply apy once `action` is applied to the platform's liquidity
+    fn supply_apy(&self, action: ReserveAction) -> Result<Rate, ProgramError>;
+    /// returns the liquidity which isn't borrowed, and can be withdrawn
+    fn available_liquidity(&self) -> u64;
+}
+
+impl RateModel for Reserve {
+    fn supply_apy(&self, action: ReserveAction) -> Result<Rate, ProgramError> {
+        Ok(self.rates_after(action)?.supply_apy)
+    }
+    fn available_liquidity(&self) -> u64 {
+        self.liquidity.available_amount
+    }
+}
+
+/// the deposits of a single standalone vault before and after rebalancing
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StandaloneAllocation {
+    pub vault_address: Pubkey,
+    pub program_type: ProgramType,
+    /// the amount currently deposited
+    pub current_balance: u64,
+    /// the amount deposited once the simulated rebalances complete
+    pub rebalanced_balance: u64,
+    /// the supply apy earned on the current balance
+    pub current_apy: Rate,
+    /// the supply apy earned on the rebalanced balance
+    pub rebalanced_apy: Rate,
+}
+
+/// a single rebalance, moving liquidity from vault a to vault b
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RebalanceMove {
+    pub vault_address_a: Pubkey,
+    pub vault_address_b: Pubkey,
+    pub vault_a_program_type: ProgramType,
+    pub vault_b_program_type: ProgramType,
+    pub amount: u64,
+}
+
+/// the outcome of a simulated rebalance
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RebalanceSimulation {
+    pub allocations: Vec<StandaloneAllocation>,
+    /// the rebalances in the order they would be executed
+    pub moves: Vec<RebalanceMove>,
+    /// the deposit weighted supply apy of the current allocation
+    pub apy_before: Rate,
+    /// the deposit weighted supply apy once the simulated rebalances complete
+    pub apy_after: Rate,
+    /// false when the simulation stopped after `MAX_SIMULATED_REBALANCES` rebalances,
+    /// while a further rebalance would still have increased the interest earned
+    pub converged: bool,
+}
+
+impl RebalanceMove {
+    /// returns the rebalance state transition account at each state the rebalance
+    /// goes through, in order, ending with the rebalance of vault a and vault b complete
+    pub fn state_transitions(&self, optimizer_vault: Pubkey) -> Vec<RebalanceStateTransitionV1> {
+        let mut transition = RebalanceStateTransitionV1 {
+            optimizer_vault,
+            vault_address_a: self.vault_address_a,
+            vault_address_b: self.vault_address_b,
+            vault_a_program_type: self.vault_a_program_type,
+            vault_b_program_type: self.vault_b_program_type,
+            ..Default::default()
+        };
+        let mut transitions = Vec::with_capacity(3);
+        while transition.next_state() != RebalanceStates::Inactive {
+            transition.state = transition.next_state();
+            match transition.state {
+                RebalanceStates::Started => transition.vault_removal_amount_a = self.amount,
+                RebalanceStates::VaultARemoved => transition.vault_supply_amount_b = self.amount,
+                _ => (),
+            }
+            transitions.push(transition.clone());
+        }
+        transitions
+    }
+}
+
+/// a standalone vault along with the rate model of the platform it lends to
+struct Standalone<'a> {
+    vault_address: Pubkey,
+    program_type: ProgramType,
+    balance: u64,
+    rate_model: &'a dyn RateModel,
+}
+
+impl Standalone<'_> {
+    /// returns the supply apy the standalone vault would earn with `allocation` deposited
+    fn supply_apy(&self, allocation: u64) -> Result<Rate, ProgramError> {
+        let action = if allocation >= self.balance {
+            ReserveAction::Deposit(allocation - self.balance)
+        } else {
+            ReserveAction::Withdraw(self.balance - allocation)
+        };
+        self.rate_model.supply_apy(action)
+    }
+    /// returns the annual interest earned with `allocation` deposited, scaled by WAD
+    fn interest(&self, allocation: u64) -> Result<u128, RebalanceSimulationError> {
+        (allocation as u128)
+            .checked_mul(self.supply_apy(allocation)?.to_scaled_val())
+            .ok_or(RebalanceSimulationError::MathOverflow)
+    }
+    /// returns the lowest balance the standalone vault can be left with, as liquidity
+    /// which is borrowed can't be withdrawn
+    fn minimum_balance(&self) -> u64 {
+        self.balance
+            .saturating_sub(self.rate_model.available_liquidity())
+    }
+}
+
+/// simulates rebalancing the multi deposit optimizer's deposits across its standalone vaults,
+/// using the given rate models keyed by standalone vault address
+pub fn simulate_multi_deposit_rebalance(
+    vault: &MultiDepositOptimizerV1,
+    rate_models: &HashMap<Pubkey, &dyn RateModel>,
+) -> std::result::Result<RebalanceSimulation, RebalanceSimulationError> {
+    let mut standalones: Vec<Standalone> = Vec::with_capacity(vault.standalone_vaults.len());
+    for cache in vault.standalone_vaults.iter() {
+        if cache.vault_address.eq(&DEFAULT_KEY)
+            || standalones
+                .iter()
+                .any(|standalone| standalone.vault_address.eq(&cache.vault_address))
+        {
+            continue;
+        }
+        standalones.push(Standalone {
+            vault_address: cache.vault_address,
+            program_type: cache.program_type,
+            balance: cache.deposited_balance,
+            rate_model: *rate_models.get(&cache.vault_address).ok_or(
+                RebalanceSimulationError::MissingRateModel(cache.vault_address),
+            )?,
+        });
+    }
+    if standalones.is_empty() {
+        return Err(RebalanceSimulationError::NoStandaloneVaults);
+    }
+
+    let mut rebalanced = *vault;
+    let mut moves = Vec::new();
+    let converged = loop {
+        let rebalance = match next_rebalance(&rebalanced, &standalones)? {
+            Some(rebalance) => rebalance,
+            None => break true,
+        };
+        if moves.len() == MAX_SIMULATED_REBALANCES {
+            break false;
+        }
+        for cache in rebalanced.standalone_vaults.iter_mut() {
+            if cache.vault_address.eq(&rebalance.vault_address_a) {
+                cache.deposited_balance -= rebalance.amount;
+            } else if cache.vault_address.eq(&rebalance.vault_address_b) {
+                cache.deposited_balance += rebalance.amount;
+            }
+        }
+        moves.push(rebalance);
+    };
+
+    let mut allocations = Vec::with_capacity(standalones.len());
+    for standalone in standalones.iter() {
+        let rebalanced_balance = balance_of(&rebalanced, &standalone.vault_address);
+        allocations.push(StandaloneAllocation {
+            vault_address: standalone.vault_address,
+            program_type: standalone.program_type,
+            current_balance: standalone.balance,
+            rebalanced_balance,
+            current_apy: standalone.supply_apy(standalone.balance)?,
+            rebalanced_apy: standalone.supply_apy(rebalanced_balance)?,
+        });
+    }
+    Ok(RebalanceSimulation {
+        apy_before: blended_apy(
+            allocations
+                .iter()
+                .map(|allocation| (allocation.current_balance, allocation.current_apy)),
+        )?,
+        apy_after: blended_apy(
+            allocations
+                .iter()
+                .map(|allocation| (allocation.rebalanced_balance, allocation.rebalanced_apy)),
+        )?,
+        allocations,
+        moves,
+        converged,
+    })
+}
+
+/// returns the rebalance from one of the top two deposits into one of the bottom two
+/// deposits which most increases the interest earned, or None if no rebalance does
+fn next_rebalance(
+    vault: &MultiDepositOptimizerV1,
+    standalones: &[Standalone],
+) -> Result<Option<RebalanceMove>, RebalanceSimulationError> {
+    let mut active: ActiveStandaloneVaults = vault.active_deposits();
+    active.sort();
+    // a rebalance needs two standalone vaults holding deposits
+    if active.len() < 2 {
+        return Ok(None);
+    }
+    let mut best: Option<(RebalanceMove, i128)> = None;
+    for vault_a in active.top_two().iter() {
+        if vault_a.deposited_balance < vault.minimum_rebalance_amount {
+            continue;
+        }
+        for vault_b in active.bottom_two().iter() {
+            if vault_b.vault_address.eq(&vault_a.vault_address)
+                || vault_b.vault_address.eq(&DEFAULT_KEY)
+            {
+                continue;
+            }
+            let (a, b) = match (
+                find_standalone(standalones, &vault_a.vault_address),
+                find_standalone(standalones, &vault_b.vault_address),
+            ) {
+                (Some(a), Some(b)) => (a, b),
+                _ => continue,
+            };
+            let (amount, gain) = best_amount(
+                a,
+                vault_a.deposited_balance,
+                b,
+                vault_b.deposited_balance,
+                vault_a
+                    .deposited_balance
+                    .saturating_sub(a.minimum_balance()),
+            )?;
+            if amount == 0 || gain <= best.map(|(_, gain)| gain).unwrap_or(0) {
+                continue;
+            }
+            best = Some((
+                RebalanceMove {
+                    vault_address_a: a.vault_address,
+                    vault_address_b: b.vault_address,
+                    vault_a_program_type: a.program_type,
+                    vault_b_program_type: b.program_type,
+                    amount,
+                },
+                gain,
+            ));
+        }
+    }
+    Ok(best.map(|(rebalance, _)| rebalance))
+}
+
+/// returns the amount, up to `max_amount`, moved from `a` holding `balance_a` to `b` holding
+/// `balance_b` which earns the most interest, along with the interest it gains.
+///
+/// the interest earned is concave in the amount moved, as supply rates fall with the
+/// liquidity supplied, so the amount is found with a ternary search
+fn best_amount(
+    a: &Standalone,
+    balance_a: u64,
+    b: &Standalone,
+    balance_b: u64,
+    max_amount: u64,
+) -> Result<(u64, i128), RebalanceSimulationError> {
+    let interest = |amount: u64| -> Result<i128, RebalanceSimulationError> {
+        let interest = a
+            .interest(balance_a - amount)?
+            .checked_add(b.interest(balance_b + amount)?)
+            .ok_or(RebalanceSimulationError::MathOverflow)?;
+        i128::try_from(interest).map_err(|_| RebalanceSimulationError::MathOverflow)
+    };
+    let (mut low, mut high) = (0, max_amount);
+    while high - low > 2 {
+        let third = (high - low) / 3;
+        if interest(low + third)? < interest(high - third)? {
+            low += third + 1;
+        } else {
+            high -= third;
+        }
+    }
+    let current = interest(0)?;
+    let mut best = (0, 0);
+    for amount in low..=high {
+        let gain = interest(amount)? - current;
+        if gain > best.1 {
+            best = (amount, gain);
+        }
+    }
+    Ok(best)
+}
+
+fn find_standalone<'a, 'b>(
+    standalones: &'a [Standalone<'b>],
+    vault_address: &Pubkey,
+) -> Option<&'a Standalone<'b>> {
+    standalones
+        .iter()
+        .find(|standalone| standalone.vault_address.eq(vault_address))
+}
+
+fn balance_of(vault: &MultiDepositOptimizerV1, vault_address: &Pubkey) -> u64 {
+    vault
+        .standalone_vaults
+        .iter()
+        .find(|cache| cache.vault_address.eq(vault_address))
+        .map(|cache| cache.deposited_balance)
+        .unwrap_or_default()
+}
+
+/// returns the supply apy weighted by the balance each apy is earned on
+fn blended_apy(
+    balances: impl Iterator<Item = (u64, Rate)>,
+) -> Result<Rate, RebalanceSimulationError> {
+    let (mut total_balance, mut total_interest) = (0_u128, 0_u128);
+    for (balance, apy) in balances {
+        total_balance += balance as u128;
+        total_interest = (balance as u128)
+            .checked_mul(apy.to_scaled_val())
+            .and_then(|interest| total_interest.checked_add(interest))
+            .ok_or(RebalanceSimulationError::MathOverflow)?;
+    }
+    Ok(total_interest
+        .checked_div(total_balance)
+        .map(Rate::from_scaled_val_big)
+        .unwrap_or_else(Rate::zero))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::accounts::multi_optimizer::StandaloneVaultCacheV1;
+    use tulipv2_sdk_common::{
+        lending::reserve::{ReserveConfig, ReserveLiquidity},
+        math::decimal::Decimal,
+    };
+
+    fn mock_reserve(available_amount: u64, borrowed_amount: u64) -> Reserve {
+        Reserve {
+            liquidity: ReserveLiquidity {
+                available_amount,
+                borrowed_amount_wads: Decimal::from(borrowed_amount),
+                platform_fees: 10,
+                ..Default::default()
+            },
+            config: ReserveConfig {
+                optimal_utilization_rate: 80,
+                degen_utilization_rate: 90,
+                min_borrow_rate: 0,
+                optimal_borrow_rate: 10,
+                degen_borrow_rate: 30,
+                max_borrow_rate: 100,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    /// a platform paying the same supply apy however much is deposited
+    struct FixedRate {
+        apy: Rate,
+        available_liquidity: u64,
+    }
+
+    impl RateModel for FixedRate {
+        fn supply_apy(&self, _action: ReserveAction) -> Result<Rate, ProgramError> {
+            Ok(self.apy)
+        }
+        fn available_liquidity(&self) -> u64 {
+            self.available_liquidity
+        }
+    }
+
+    #[test]
+    fn test_simulate_multi_deposit_rebalance() {
+        let tulip = Pubkey::new_unique();
+        let solend = Pubkey::new_unique();
+        let mango = Pubkey::new_unique();
+        let mut vault = MultiDepositOptimizerV1::default();
+        for (standalone, (vault_address, program_type, deposited_balance)) in
+            vault.standalone_vaults.iter_mut().zip([
+                (tulip, ProgramType::SplUnmodified, 3_000_000),
+                (solend, ProgramType::SplModifiedSolend, 1_000_000),
+                (mango, ProgramType::MangoV3, 2_000_000),
+            ])
+        {
+            *standalone = StandaloneVaultCacheV1 {
+                vault_address,
+                program_type,
+                deposited_balance,
+                ..Default::default()
+            };
+        }
+        // 50% utilization
+        let tulip_reserve = mock_reserve(20_000_000, 20_000_000);
+        // 75% utilization
+        let solend_reserve = mock_reserve(10_000_000, 30_000_000);
+        let mango_rate = FixedRate {
+            apy: Rate::from_percent(1),
+            available_liquidity: 10_000_000,
+        };
+        let mut rate_models: HashMap<Pubkey, &dyn RateModel> = HashMap::new();
+        rate_models.insert(tulip, &tulip_reserve);
+        rate_models.insert(solend, &solend_reserve);
+        rate_models.insert(mango, &mango_rate);
+
+        let simulation = simulate_multi_deposit_rebalance(&vault, &rate_models).unwrap();
+        assert_eq!(simulation.allocations.len(), 3);
+        assert!(simulation.allocations[1].current_apy > simulation.allocations[0].current_apy);
+        assert_eq!(simulation.allocations[2].current_apy, Rate::from_percent(1));
+        assert_eq!(
+            simulation
+                .allocations
+                .iter()
+                .map(|allocation| allocation.rebalanced_balance)
+                .sum::<u64>(),
+            6_000_000
+        );
+        // mango is among the top two deposits and earns the least, so it is moved into
+        // solend, the smallest deposit, which earns the most
+        let rebalance = simulation.moves[0];
+        assert_eq!(rebalance.vault_address_a, mango);
+        assert_eq!(rebalance.vault_address_b, solend);
+        assert_eq!(rebalance.vault_a_program_type, ProgramType::MangoV3);
+        assert_eq!(rebalance.amount, 2_000_000);
+        // once mango holds no deposits, only tulip can be rebalanced into solend
+        assert!(simulation.moves[1..]
+            .iter()
+            .all(|rebalance| rebalance.vault_address_a == tulip
+                && rebalance.vault_address_b == solend));
+        assert_eq!(simulation.allocations[2].rebalanced_balance, 0);
+        assert!(simulation.allocations[1].rebalanced_balance > 3_000_000);
+        assert!(simulation.apy_after > simulation.apy_before);
+        assert!(simulation.converged);
+        assert!(simulation.moves.len() < MAX_SIMULATED_REBALANCES);
+
+        let transitions = rebalance.state_transitions(Pubkey::new_unique());
+        assert_eq!(
+            transitions
+                .iter()
+                .map(RebalanceStateTransitionV1::current_state)
+                .collect::<Vec<_>>(),
+            vec![
+                RebalanceStates::Started,
+                RebalanceStates::VaultARemoved,
+                RebalanceStates::VaultABRebalanced
+            ]
+        );
+        assert_eq!(transitions[0].remove_amount(), rebalance.amount);
+        assert_eq!(transitions[1].supply_amount(), rebalance.amount);
+
+        // vaults holding less than the minimum rebalance amount aren't rebalanced out of
+        vault.minimum_rebalance_amount = 3_000_001;
+        let simulation = simulate_multi_deposit_rebalance(&vault, &rate_models).unwrap();
+        assert!(simulation.moves.is_empty());
+        assert_eq!(simulation.apy_after, simulation.apy_before);
+        assert!(simulation.converged);
+
+        rate_models.remove(&solend);
+        assert_eq!(
+            simulate_multi_deposit_rebalance(&vault, &rate_models),
+            Err(RebalanceSimulationError::MissingRateModel(solend))
+        );
+
+        // the interest earned on a deposit must fit in a u128
+        let overflowing_rate = FixedRate {
+            apy: Rate::from_scaled_val_big(u128::MAX),
+            available_liquidity: 10_000_000,
+        };
+        rate_models.insert(solend, &overflowing_rate);
+        assert_eq!(
+            simulate_multi_deposit_rebalance(&vault, &rate_models),
+            Err(RebalanceSimulationError::MathOverflow)
+        );
+    }
+}
@@ -0,0 +1,184 @@
+//! routes withdrawals from multi deposit optimizer vaults across their standalone vaults.
+//!
+//! each `withdraw_multi_deposit_optimizer_vault` instruction burns multi deposit optimizer
+//! shares in exchange for underlying withdrawn from a single standalone vault, which can only
+//! provide as much underlying as has been deposited into it. withdrawals larger than any one
+//! standalone vault's deposits are split across several, starting with the standalone vault
+//! holding the largest deposit.
+
+use crate::accounts::multi_optimizer::MultiDepositOptimizerV1;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use thiserror::Error;
+use tulipv2_sdk_common::config::strategy::{traits::MultiVaultProgramConfig, Platform};
+
+/// the platforms a multi deposit optimizer's standalone vaults may lend to
+pub const PLATFORMS: [Platform; 3] = [Platform::MangoV3, Platform::Tulip, Platform::Solend];
+
+/// errors returned when routing a withdrawal
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum WithdrawRouteError {
+    #[error("the vault has no shares or deposits")]
+    EmptyVault,
+    #[error("{requested} shares requested, but the standalone vaults can only redeem {available}")]
+    InsufficientLiquidity { requested: u64, available: u64 },
+    #[error("standalone vault {0} is not a standalone vault of the multi deposit optimizer")]
+    UnknownStandaloneVault(Pubkey),
+    #[error("failed to build the withdraw instruction for {0:?}")]
+    InvalidInstruction(Platform),
+}
+
+/// a portion of a withdrawal redeemed from a single standalone vault
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WithdrawRoute {
+    pub platform: Platform,
+    pub standalone_vault: Pubkey,
+    /// the multi deposit optimizer shares burned
+    pub shares: u64,
+    /// the underlying the burned shares are worth
+    pub underlying: u64,
+}
+
+/// splits burning `shares` of the multi deposit optimizer across its standalone vaults,
+/// withdrawing as much as possible from the standalone vaults with the largest deposits
+pub fn route_multi_deposit_withdraw(
+    vault: &MultiDepositOptimizerV1,
+    config: &dyn MultiVaultProgramConfig,
+    shares: u64,
+) -> std::result::Result<Vec<WithdrawRoute>, WithdrawRouteError> {
+    let total_shares = vault.base.total_shares as u128;
+    let total_deposited = vault.base.total_deposited_balance as u128;
+    if total_shares == 0 || total_deposited == 0 {
+        return Err(WithdrawRouteError::EmptyVault);
+    }
+    let mut standalone_vaults = vault.active_deposits();
+    standalone_vaults.sort();
+
+    let mut routes = Vec::with_capacity(standalone_vaults.len());
+    let mut remaining = shares;
+    for standalone in standalone_vaults.vaults().iter().rev() {
+        if remaining == 0 {
+            break;
+        }
+        // shares are rounded down so that their underlying value never exceeds the deposit
+        let redeemable = (standalone.deposited_balance as u128 * total_shares / total_deposited)
+            .min(u64::MAX as u128) as u64;
+        let route_shares = remaining.min(redeemable);
+        if route_shares == 0 {
+            continue;
+        }
+        let platform = PLATFORMS
+            .into_iter()
+            .find(|platform| {
+                config.standalone_config(*platform).account() == standalone.vault_address
+            })
+            .ok_or(WithdrawRouteError::UnknownStandaloneVault(
+                standalone.vault_address,
+            ))?;
+        routes.push(WithdrawRoute {
+            platform,
+            standalone_vault: standalone.vault_address,
+            shares: route_shares,
+            underlying: (route_shares as u128 * total_deposited / total_shares) as u64,
+        });
+        remaining -= route_shares;
+    }
+    if remaining > 0 {
+        return Err(WithdrawRouteError::InsufficientLiquidity {
+            requested: shares,
+            available: shares - remaining,
+        });
+    }
+    Ok(routes)
+}
+
+/// returns the instructions burning `shares` of the multi deposit optimizer owned by `user`,
+/// one for each standalone vault the withdrawal is routed through
+pub fn new_routed_withdraw_multi_deposit_optimizer_ixs(
+    vault: &MultiDepositOptimizerV1,
+    config: &dyn MultiVaultProgramConfig,
+    user: Pubkey,
+    shares: u64,
+) -> std::result::Result<Vec<Instruction>, WithdrawRouteError> {
+    route_multi_deposit_withdraw(vault, config, shares)?
+        .into_iter()
+        .map(|route| {
+            config
+                .withdraw_multi_deposit_optimizer_vault(user, route.platform)
+                .ok()
+                .and_then(|withdraw| withdraw.instruction(route.shares))
+                .ok_or(WithdrawRouteError::InvalidInstruction(route.platform))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::accounts::multi_optimizer::StandaloneVaultCacheV1;
+    use tulipv2_sdk_common::config::strategy::StrategyVaults;
+
+    #[test]
+    fn test_route_multi_deposit_withdraw() {
+        let config = StrategyVaults::USDCv1.multi_deposit_config();
+        let mut vault = MultiDepositOptimizerV1::default();
+        // each share is worth 2 underlying
+        vault.base.total_shares = 500;
+        vault.base.total_deposited_balance = 1_000;
+        for (standalone, (platform, deposited_balance)) in vault.standalone_vaults.iter_mut().zip([
+            (Platform::MangoV3, 100),
+            (Platform::Tulip, 600),
+            (Platform::Solend, 300),
+        ]) {
+            *standalone = StandaloneVaultCacheV1 {
+                vault_address: config.standalone_config(platform).account(),
+                deposited_balance,
+                ..Default::default()
+            };
+        }
+
+        let routes = route_multi_deposit_withdraw(&vault, config.as_ref(), 200).unwrap();
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].platform, Platform::Tulip);
+        assert_eq!(routes[0].underlying, 400);
+
+        let routes = route_multi_deposit_withdraw(&vault, config.as_ref(), 460).unwrap();
+        assert_eq!(
+            routes
+                .iter()
+                .map(|route| (route.platform, route.shares))
+                .collect::<Vec<_>>(),
+            vec![
+                (Platform::Tulip, 300),
+                (Platform::Solend, 150),
+                (Platform::MangoV3, 10)
+            ]
+        );
+        assert_eq!(
+            route_multi_deposit_withdraw(&vault, config.as_ref(), 501),
+            Err(WithdrawRouteError::InsufficientLiquidity {
+                requested: 501,
+                available: 500
+            })
+        );
+
+        let user = Pubkey::new_unique();
+        let ixs =
+            new_routed_withdraw_multi_deposit_optimizer_ixs(&vault, config.as_ref(), user, 460)
+                .unwrap();
+        assert_eq!(ixs.len(), 3);
+        assert_eq!(
+            ixs[1].accounts[3].pubkey,
+            config.standalone_config(Platform::Solend).account()
+        );
+
+        vault.standalone_vaults[0].vault_address = Pubkey::new_unique();
+        vault.standalone_vaults[0].deposited_balance = 10_000;
+        assert_eq!(
+            route_multi_deposit_withdraw(&vault, config.as_ref(), 1),
+            Err(WithdrawRouteError::UnknownStandaloneVault(
+                vault.standalone_vaults[0].vault_address
+            ))
+        );
+    }
+}
@@ -0,0 +1,456 @@
+//! plans the multi-stage withdrawal of lp tokens from orca vaults.
+//!
+//! non double dip vaults are withdrawn from with `withdraw_orca_vault`, which burns the shares
+//! and reverts the vault's farm tokens into lp tokens, followed by `withdraw_orca_vault_remove_liq`.
+//! double dip vaults first revert the double dip farm tokens with `withdraw_orca_vault_dd_stage_one`,
+//! then the non double dip farm tokens with `withdraw_orca_vault_dd_stage_two`, before removing
+//! liquidity.
+//!
+//! the first stage creates an `EphemeralTrackingV1` account which carries the withdrawal between
+//! stages. it is `configured` once the first stage has completed, allowed to withdraw (`can_withdraw`)
+//! once every farm token has been reverted into `liq_to_remove` lp tokens, and closed when liquidity
+//! is removed, which allows a half finished withdrawal to be resumed from the account's state.
+
+use crate::accounts::{
+    derive_ephemeral_tracking_address,
+    orca_vault::{OrcaDoubleDipVaultV1, OrcaVaultDataV1, OrcaVaultV1},
+    tracking::EphemeralTrackingV1,
+    vault_base::VaultBaseV1,
+};
+use crate::instructions::orca::{
+    new_withdraw_orca_vault_dd_stage_one_ix, new_withdraw_orca_vault_dd_stage_two_ix,
+    new_withdraw_orca_vault_remove_liq_ix, new_withdraw_orca_vault_stage_one_ix,
+};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use spl_associated_token_account::get_associated_token_address;
+use thiserror::Error;
+use tulipv2_sdk_common::config::{ORCA_AQUAFARM_PROGRAM, ORCA_SWAP_PROGRAM};
+
+/// a single instruction of an orca vault withdrawal
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrcaWithdrawStage {
+    /// burns the shares and reverts the farm tokens of a non double dip vault
+    StageOne,
+    /// burns the shares and reverts the double dip farm tokens of a double dip vault
+    DoubleDipStageOne,
+    /// reverts the non double dip farm tokens of a double dip vault
+    DoubleDipStageTwo,
+    /// removes liquidity for the reverted lp tokens and closes the ephemeral tracking account.
+    /// the program removes the tracking account's `liq_to_remove` lp tokens, so the
+    /// instruction takes no amount
+    RemoveLiquidity,
+}
+
+/// errors returned when planning an orca vault withdrawal
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum OrcaWithdrawError {
+    #[error("the ephemeral tracking account belongs to {found}, not {expected}")]
+    TrackingAuthority { expected: Pubkey, found: Pubkey },
+    #[error("no stage of a {vault} vault withdrawal continues from an ephemeral tracking account with configured {configured} and can_withdraw {can_withdraw}")]
+    Unresumable {
+        vault: &'static str,
+        configured: u8,
+        can_withdraw: u8,
+    },
+    #[error("failed to build the {0:?} instruction")]
+    InvalidInstruction(OrcaWithdrawStage),
+}
+
+/// accounts specific to double dip vaults
+#[derive(Clone, Copy)]
+pub struct OrcaDoubleDipAccounts {
+    pub farm_data: OrcaVaultDataV1,
+    pub withdraw_queue: Pubkey,
+    pub fee_collector_token_account: Pubkey,
+}
+
+/// plans the withdrawal of an authority's shares from an orca vault
+#[derive(Clone, Copy)]
+pub struct OrcaWithdrawPlanner {
+    pub authority: Pubkey,
+    pub vault: Pubkey,
+    pub vault_pda: Pubkey,
+    pub shares_mint: Pubkey,
+    pub farm_data: OrcaVaultDataV1,
+    /// present for double dip vaults
+    pub double_dip: Option<OrcaDoubleDipAccounts>,
+    pub ephemeral_tracking_account: Pubkey,
+    /// the pool's fee account
+    pub swap_pool_fee: Pubkey,
+    /// the fee wallet's reward token account
+    pub fee_collector_token_account: Pubkey,
+    /// the authority's shares token account, from which shares are burned
+    pub burning_shares_token_account: Pubkey,
+    /// the authority's lp token account, which receives the reverted lp tokens
+    pub underlying_token_account: Pubkey,
+    /// the authority's token a and token b accounts, which receive the removed liquidity
+    pub token_a_account: Pubkey,
+    pub token_b_account: Pubkey,
+}
+
+impl OrcaWithdrawPlanner {
+    /// returns the planner for withdrawing from a non double dip vault, using the
+    /// authority's associated token accounts
+    pub fn new(
+        authority: Pubkey,
+        vault: Pubkey,
+        vault_account: &OrcaVaultV1,
+        swap_pool_fee: Pubkey,
+    ) -> Self {
+        Self::with_farm_data(
+            authority,
+            vault,
+            &vault_account.base,
+            vault_account.farm_data,
+            swap_pool_fee,
+        )
+    }
+
+    /// returns the planner for withdrawing from a double dip vault, using the
+    /// authority's associated token accounts
+    pub fn new_double_dip(
+        authority: Pubkey,
+        vault: Pubkey,
+        vault_account: &OrcaDoubleDipVaultV1,
+        swap_pool_fee: Pubkey,
+    ) -> Self {
+        let mut planner = Self::with_farm_data(
+            authority,
+            vault,
+            &vault_account.base,
+            vault_account.farm_data,
+            swap_pool_fee,
+        );
+        planner.double_dip = Some(OrcaDoubleDipAccounts {
+            farm_data: vault_account.dd_farm_data,
+            withdraw_queue: vault_account.dd_withdraw_queue,
+            fee_collector_token_account: get_associated_token_address(
+                &vault_account.base.fees.fee_wallet,
+                &vault_account.dd_farm_data.reward_token_mint,
+            ),
+        });
+        planner
+    }
+
+    fn with_farm_data(
+        authority: Pubkey,
+        vault: Pubkey,
+        base: &VaultBaseV1,
+        farm_data: OrcaVaultDataV1,
+        swap_pool_fee: Pubkey,
+    ) -> Self {
+        Self {
+            authority,
+            vault,
+            vault_pda: base.pda,
+            shares_mint: base.shares_mint,
+            farm_data,
+            double_dip: None,
+            ephemeral_tracking_account: derive_ephemeral_tracking_address(
+                &vault,
+                &authority,
+                &crate::ID,
+            )
+            .0,
+            swap_pool_fee,
+            fee_collector_token_account: get_associated_token_address(
+                &base.fees.fee_wallet,
+                &farm_data.reward_token_mint,
+            ),
+            burning_shares_token_account: get_associated_token_address(
+                &authority,
+                &base.shares_mint,
+            ),
+            underlying_token_account: get_associated_token_address(
+                &authority,
+                &farm_data.swap_pool_mint,
+            ),
+            token_a_account: get_associated_token_address(&authority, &farm_data.token_a_mint),
+            token_b_account: get_associated_token_address(&authority, &farm_data.token_b_mint),
+        }
+    }
+
+    /// returns the stages left to complete the withdrawal, given the authority's ephemeral
+    /// tracking account if it exists.
+    ///
+    /// returns `OrcaWithdrawError::TrackingAuthority` if the tracking account belongs to
+    /// someone else, and `OrcaWithdrawError::Unresumable` if it hasn't completed the first
+    /// stage, or is `configured` without `can_withdraw` for a non double dip vault, whose
+    /// first stage sets both
+    pub fn stages(
+        &self,
+        ephemeral_tracking: Option<&EphemeralTrackingV1>,
+    ) -> std::result::Result<Vec<OrcaWithdrawStage>, OrcaWithdrawError> {
+        let tracking = match ephemeral_tracking {
+            None if self.double_dip.is_some() => {
+                return Ok(vec![
+                    OrcaWithdrawStage::DoubleDipStageOne,
+                    OrcaWithdrawStage::DoubleDipStageTwo,
+                    OrcaWithdrawStage::RemoveLiquidity,
+                ])
+            }
+            None => {
+                return Ok(vec![
+                    OrcaWithdrawStage::StageOne,
+                    OrcaWithdrawStage::RemoveLiquidity,
+                ])
+            }
+            Some(tracking) => tracking,
+        };
+        if tracking.authority != self.authority {
+            return Err(OrcaWithdrawError::TrackingAuthority {
+                expected: self.authority,
+                found: tracking.authority,
+            });
+        }
+        if tracking.can_withdraw != 0 {
+            Ok(vec![OrcaWithdrawStage::RemoveLiquidity])
+        } else if self.double_dip.is_some() && tracking.configured != 0 {
+            Ok(vec![
+                OrcaWithdrawStage::DoubleDipStageTwo,
+                OrcaWithdrawStage::RemoveLiquidity,
+            ])
+        } else {
+            Err(OrcaWithdrawError::Unresumable {
+                vault: if self.double_dip.is_some() {
+                    "double dip"
+                } else {
+                    "non double dip"
+                },
+                configured: tracking.configured,
+                can_withdraw: tracking.can_withdraw,
+            })
+        }
+    }
+
+    /// returns the remaining withdrawal instructions, one transaction per stage, burning
+    /// `amount` shares if the withdrawal has not been started.
+    ///
+    /// each stage reads the ephemeral tracking account written by the previous stage, so the
+    /// transactions must be confirmed in order
+    pub fn transactions(
+        &self,
+        amount: u64,
+        ephemeral_tracking: Option<&EphemeralTrackingV1>,
+    ) -> std::result::Result<Vec<Vec<Instruction>>, OrcaWithdrawError> {
+        self.stages(ephemeral_tracking)?
+            .into_iter()
+            .map(|stage| {
+                self.instruction(stage, amount)
+                    .map(|ix| vec![ix])
+                    .ok_or(OrcaWithdrawError::InvalidInstruction(stage))
+            })
+            .collect()
+    }
+
+    /// returns the instruction for `stage`, where `amount` is the number of shares burned
+    /// by the first stage
+    pub fn instruction(&self, stage: OrcaWithdrawStage, amount: u64) -> Option<Instruction> {
+        let farm_data = &self.farm_data;
+        let swap_authority = self.swap_authority();
+        match stage {
+            OrcaWithdrawStage::StageOne => new_withdraw_orca_vault_stage_one_ix(
+                self.authority,
+                self.vault,
+                self.vault_pda,
+                self.burning_shares_token_account,
+                self.underlying_token_account,
+                farm_data.vault_farm_token_account,
+                farm_data.vault_reward_token_account,
+                farm_data.vault_swap_token_account,
+                farm_data.global_reward_token_vault,
+                farm_data.pool_swap_token_a,
+                farm_data.pool_swap_token_b,
+                farm_data.global_farm,
+                farm_data.user_farm_addr,
+                convert_authority(&farm_data.global_farm),
+                farm_data.pool_swap_account,
+                swap_authority,
+                farm_data.swap_pool_mint,
+                farm_data.farm_token_mint,
+                self.shares_mint,
+                self.swap_pool_fee,
+                ORCA_SWAP_PROGRAM,
+                ORCA_AQUAFARM_PROGRAM,
+                self.ephemeral_tracking_account,
+                self.fee_collector_token_account,
+                amount,
+            ),
+            OrcaWithdrawStage::DoubleDipStageOne => {
+                let double_dip = self.double_dip.as_ref()?;
+                let dd_farm_data = &double_dip.farm_data;
+                new_withdraw_orca_vault_dd_stage_one_ix(
+                    self.authority,
+                    self.vault,
+                    self.vault_pda,
+                    self.burning_shares_token_account,
+                    self.underlying_token_account,
+                    dd_farm_data.vault_farm_token_account,
+                    dd_farm_data.vault_reward_token_account,
+                    dd_farm_data.vault_swap_token_account,
+                    dd_farm_data.global_reward_token_vault,
+                    farm_data.pool_swap_token_a,
+                    farm_data.pool_swap_token_b,
+                    dd_farm_data.global_farm,
+                    dd_farm_data.user_farm_addr,
+                    convert_authority(&dd_farm_data.global_farm),
+                    farm_data.pool_swap_account,
+                    swap_authority,
+                    farm_data.swap_pool_mint,
+                    dd_farm_data.farm_token_mint,
+                    self.shares_mint,
+                    self.swap_pool_fee,
+                    ORCA_SWAP_PROGRAM,
+                    ORCA_AQUAFARM_PROGRAM,
+                    self.ephemeral_tracking_account,
+                    double_dip.fee_collector_token_account,
+                    double_dip.withdraw_queue,
+                    amount,
+                )
+            }
+            OrcaWithdrawStage::DoubleDipStageTwo if self.double_dip.is_some() => {
+                new_withdraw_orca_vault_dd_stage_two_ix(
+                    self.authority,
+                    self.vault,
+                    self.vault_pda,
+                    self.burning_shares_token_account,
+                    self.underlying_token_account,
+                    farm_data.vault_farm_token_account,
+                    farm_data.vault_reward_token_account,
+                    farm_data.vault_swap_token_account,
+                    farm_data.global_reward_token_vault,
+                    farm_data.pool_swap_token_a,
+                    farm_data.pool_swap_token_b,
+                    farm_data.global_farm,
+                    farm_data.user_farm_addr,
+                    convert_authority(&farm_data.global_farm),
+                    farm_data.pool_swap_account,
+                    swap_authority,
+                    farm_data.swap_pool_mint,
+                    farm_data.farm_token_mint,
+                    self.shares_mint,
+                    self.swap_pool_fee,
+                    ORCA_SWAP_PROGRAM,
+                    ORCA_AQUAFARM_PROGRAM,
+                    self.ephemeral_tracking_account,
+                    self.fee_collector_token_account,
+                )
+            }
+            OrcaWithdrawStage::DoubleDipStageTwo => None,
+            OrcaWithdrawStage::RemoveLiquidity => new_withdraw_orca_vault_remove_liq_ix(
+                self.authority,
+                self.vault,
+                self.vault_pda,
+                self.underlying_token_account,
+                self.token_a_account,
+                self.token_b_account,
+                farm_data.pool_swap_token_a,
+                farm_data.pool_swap_token_b,
+                ORCA_SWAP_PROGRAM,
+                farm_data.pool_swap_account,
+                swap_authority,
+                farm_data.swap_pool_mint,
+                self.swap_pool_fee,
+                self.ephemeral_tracking_account,
+                self.shares_mint,
+                self.double_dip.is_some(),
+            ),
+        }
+    }
+
+    /// the authority of the pool's swap account
+    pub fn swap_authority(&self) -> Pubkey {
+        Pubkey::find_program_address(
+            &[self.farm_data.pool_swap_account.as_ref()],
+            &ORCA_SWAP_PROGRAM,
+        )
+        .0
+    }
+}
+
+/// the authority used by the aquafarm program to convert base tokens into farm tokens
+fn convert_authority(global_farm: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[global_farm.as_ref()], &ORCA_AQUAFARM_PROGRAM).0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bytemuck::Zeroable;
+
+    #[test]
+    fn test_orca_withdraw_stages() {
+        let authority = Pubkey::new_unique();
+        let vault = Pubkey::new_unique();
+        let mut vault_account = OrcaVaultV1::zeroed();
+        vault_account.farm_data.pool_swap_account = Pubkey::new_unique();
+        let planner =
+            OrcaWithdrawPlanner::new(authority, vault, &vault_account, Pubkey::new_unique());
+        let dd_planner = OrcaWithdrawPlanner::new_double_dip(
+            authority,
+            vault,
+            &OrcaDoubleDipVaultV1::zeroed(),
+            Pubkey::new_unique(),
+        );
+
+        let txs = planner.transactions(1_000, None).unwrap();
+        assert_eq!(txs.len(), 2);
+        assert!(txs.iter().all(|tx| tx.len() == 1));
+        assert_eq!(
+            txs[0][0].accounts[23].pubkey,
+            planner.ephemeral_tracking_account
+        );
+        // the double dip flag of the remove liquidity instruction
+        assert_eq!(txs[1][0].data[8], 0);
+        assert_eq!(
+            dd_planner.stages(None),
+            Ok(vec![
+                OrcaWithdrawStage::DoubleDipStageOne,
+                OrcaWithdrawStage::DoubleDipStageTwo,
+                OrcaWithdrawStage::RemoveLiquidity,
+            ])
+        );
+        let txs = dd_planner.transactions(1_000, None).unwrap();
+        assert_eq!(txs.len(), 3);
+        assert_eq!(txs[2][0].data[8], 1);
+        assert!(planner
+            .instruction(OrcaWithdrawStage::DoubleDipStageTwo, 0)
+            .is_none());
+
+        // resuming a withdrawal whose double dip farm tokens have been reverted
+        let mut tracking = EphemeralTrackingV1::zeroed();
+        tracking.authority = authority;
+        tracking.configured = 1;
+        assert_eq!(
+            dd_planner.stages(Some(&tracking)),
+            Ok(vec![
+                OrcaWithdrawStage::DoubleDipStageTwo,
+                OrcaWithdrawStage::RemoveLiquidity,
+            ])
+        );
+        assert_eq!(
+            planner.stages(Some(&tracking)),
+            Err(OrcaWithdrawError::Unresumable {
+                vault: "non double dip",
+                configured: 1,
+                can_withdraw: 0,
+            })
+        );
+        tracking.can_withdraw = 1;
+        assert_eq!(
+            dd_planner.stages(Some(&tracking)),
+            Ok(vec![OrcaWithdrawStage::RemoveLiquidity])
+        );
+        assert_eq!(planner.transactions(0, Some(&tracking)).unwrap().len(), 1);
+        let found = Pubkey::new_unique();
+        tracking.authority = found;
+        assert_eq!(
+            planner.stages(Some(&tracking)),
+            Err(OrcaWithdrawError::TrackingAuthority {
+                expected: authority,
+                found,
+            })
+        );
+    }
+}
@@ -8,6 +8,9 @@ use crate::accounts::{
 };
 
 use anchor_lang::solana_program::pubkey::Pubkey;
+use tulipv2_sdk_common::config::deposit_tracking::issue_shares::DepositAddresses;
+use tulipv2_sdk_common::config::deposit_tracking::register::RegisterDepositTrackingAddresses;
+use tulipv2_sdk_common::config::deposit_tracking::traits::{IssueShares, RegisterDepositTracking};
 
 use super::VaultBaseConfig;
 
@@ -142,6 +145,23 @@ impl QuarryVaultConfig {
             miner_token_account: quarry_miner_token_account,
         }
     }
+    pub fn register_deposit_tracking(&self, authority: Pubkey) -> impl RegisterDepositTracking {
+        RegisterDepositTrackingAddresses::new(
+            authority,
+            self.vault,
+            self.shares_mint,
+            self.underlying_mint,
+        )
+    }
+    pub fn issue_shares(&self, authority: Pubkey) -> impl IssueShares {
+        DepositAddresses::new(
+            authority,
+            self.vault,
+            self.pda,
+            self.shares_mint,
+            self.underlying_mint,
+        )
+    }
 }
 
 impl VaultBaseConfig for QuarryVaultConfig {